@@ -0,0 +1,373 @@
+//! Function argument expansion. Lua passes all user arguments at once, so everything that happens to them
+//! before the function body (conversion, default values, table arguments, checks) is generated here.
+//!
+//! Arguments are converted one by one from the passed values (instead of letting mlua convert a tuple), so that
+//! conversion errors can name the function and the argument that failed.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use shared::funcs::{is_option_ty, is_variadic_ty, ArgCheck, FuncArg, ParsedFunc, SelfReceiver};
+
+/// How Lua passes user arguments to a binding
+pub enum ArgPack {
+    /// Any amount of values (`mlua::MultiValue`), like in functions and methods
+    Multi,
+    /// A single value (`mlua::Value`), like in field setters
+    Single,
+}
+
+/// Argument tokens of a binding, split the way mlua expects them
+#[derive(Default)]
+pub struct ArgTokens {
+    /// Names of the required arguments (`&Lua` and `&Self`)
+    pub req_arg_names: Vec<TokenStream2>,
+    /// Name and type of the parameter that receives all user arguments
+    pub user_args_name: TokenStream2,
+    pub user_args_ty: TokenStream2,
+    /// Statements that have to run before the body (i.e. converting arguments and substituting default values)
+    pub prelude: Vec<TokenStream2>,
+    /// Arguments passed to the original function, in its own order (only used by kept functions)
+    pub call_args: Vec<TokenStream2>,
+    /// Trait bounds of the argument conversions (i.e. `T: mlua::FromLua`), needed by generic functions
+    pub conversion_bounds: Vec<TokenStream2>,
+}
+
+impl ArgTokens {
+    /// Prepend the argument prelude to a binding body
+    pub fn with_prelude(&self, body: TokenStream2) -> TokenStream2 {
+        if self.prelude.is_empty() {
+            return body;
+        }
+        let prelude = &self.prelude;
+        quote! { { #(#prelude)* #body } }
+    }
+}
+
+/// A user argument, along with the bindings it goes through in the prelude
+struct UserArg<'a> {
+    arg: &'a FuncArg,
+    /// Position of the argument in Lua (starting from 1)
+    pos: usize,
+    /// The pattern the converted value is bound to
+    binding: TokenStream2,
+    /// The binding that holds the final value (after the default is substituted)
+    value: TokenStream2,
+}
+
+impl UserArg<'_> {
+    /// Name of the argument for messages. Unnamed (destructured) arguments use their whole pattern
+    fn name(&self) -> String {
+        match self.arg.ident() {
+            Some(ident) => ident.to_string(),
+            None => self.arg.name.to_token_stream().to_string(),
+        }
+    }
+
+    /// The type the Lua value is converted into. Arguments with defaults are optional
+    fn converted_ty(&self) -> TokenStream2 {
+        let ty = &self.arg.ty;
+        match self.arg.default {
+            Some(_) => quote! { ::std::option::Option<#ty> },
+            None => ty.to_token_stream(),
+        }
+    }
+}
+
+/// Split the function arguments into [`ArgTokens`].
+///
+/// `lua_name` is the name of the function as seen from Lua (i.e. `Vector.new`), which is used in error messages.
+pub fn arg_tokens(func: &ParsedFunc, lua_name: &str, pack: ArgPack) -> ArgTokens {
+    let mut tokens = ArgTokens::default();
+    let mut lua_binding = None;
+
+    // Lua counts the `self` argument of methods as well, which is the only required argument besides `&Lua`
+    let first_pos = func.req_arg_count();
+    let mut user_args = Vec::new();
+
+    for (ind, arg) in func.args.iter().enumerate() {
+        if arg.required {
+            // Required arguments always go in the same order: `&Lua`, then `&Self`. We name the Lua state
+            // ourselves, since it's needed for conversions.
+            if ind == 0 {
+                tokens.req_arg_names.push(quote! { __lua });
+                if !arg.inserted {
+                    lua_binding = Some(arg);
+                    if func.keep {
+                        tokens.call_args.push(quote! { __lua });
+                    }
+                }
+            } else if func.keep {
+                match arg.inserted {
+                    true => tokens.req_arg_names.push(quote! { _ }),
+                    false => {
+                        tokens.req_arg_names.push(quote! { this });
+                        // Methods that take `self` by value get a clone of the userdata
+                        tokens.call_args.push(match func.receiver {
+                            Some(SelfReceiver::Value { .. }) => {
+                                quote! { ::std::clone::Clone::clone(this) }
+                            }
+                            _ => quote! { this },
+                        });
+                    }
+                }
+            } else {
+                tokens.req_arg_names.push(arg.name.to_token_stream());
+            }
+            continue;
+        }
+
+        // Kept functions can't reuse user argument patterns (they can be destructured or ignored), so every
+        // argument gets our own name instead. The same goes for arguments with defaults, since user patterns
+        // can't be optional.
+        let arg_name = format_ident!("arg{ind}").to_token_stream();
+        let binding = match func.keep || arg.default.is_some() {
+            true => arg_name.clone(),
+            false => arg.name.to_token_stream(),
+        };
+        // Checked arguments are always named, so we can refer to them after the prelude
+        let value = match (func.keep, arg.ident()) {
+            (true, _) => arg_name.clone(),
+            (false, Some(ident)) => ident.to_token_stream(),
+            (false, None) => arg.name.to_token_stream(),
+        };
+        if func.keep {
+            tokens.call_args.push(arg_name);
+        }
+
+        let pos = match func.table_args {
+            true => first_pos,
+            false => first_pos + user_args.len(),
+        };
+        user_args.push(UserArg {
+            arg,
+            pos,
+            binding,
+            value,
+        });
+    }
+
+    let location = error_location(func);
+    let (args_name, args_ty, args_iter) = match pack {
+        ArgPack::Multi => (
+            quote! { __args },
+            quote! { ::mlua::MultiValue },
+            quote! { ::std::iter::IntoIterator::into_iter(__args) },
+        ),
+        ArgPack::Single => (
+            quote! { __arg },
+            quote! { ::mlua::Value },
+            quote! { ::std::iter::once(__arg) },
+        ),
+    };
+    tokens.user_args_name = args_name;
+    tokens.user_args_ty = args_ty;
+
+    if !user_args.is_empty() {
+        tokens.prelude.push(quote! { let mut __args = #args_iter; });
+    }
+    let next_value = quote! {
+        ::std::option::Option::unwrap_or(::std::iter::Iterator::next(&mut __args), ::mlua::Value::Nil)
+    };
+
+    if func.table_args {
+        // All arguments are taken from a single table, by their names
+        let error = conversion_error(lua_name, first_pos, None, &location);
+        let table = convert_value(next_value, quote! { ::mlua::Table }, error);
+        tokens
+            .prelude
+            .push(quote! { let __table_args: ::mlua::Table = #table; });
+
+        for user_arg in user_args.iter() {
+            let key = user_arg.name();
+            let ty = user_arg.converted_ty();
+            tokens
+                .conversion_bounds
+                .push(quote! { #ty: ::mlua::FromLua });
+            let error = conversion_error(lua_name, user_arg.pos, Some(&key), &location);
+            let field = quote! { __table_args.get::<::mlua::Value>(#key)? };
+            let binding = &user_arg.binding;
+            let converted = convert_value(field, ty.clone(), error);
+            tokens
+                .prelude
+                .push(quote! { let #binding: #ty = #converted; });
+        }
+    } else {
+        for user_arg in user_args.iter() {
+            let name = user_arg.name();
+            let error = conversion_error(lua_name, user_arg.pos, Some(&name), &location);
+            let (binding, ty) = (&user_arg.binding, user_arg.converted_ty());
+
+            // A variadic argument takes all the remaining values
+            let converted = match is_variadic_ty(&user_arg.arg.ty) {
+                true => {
+                    tokens
+                        .conversion_bounds
+                        .push(quote! { #ty: ::mlua::FromLuaMulti });
+                    convert_values(
+                        quote! { ::std::iter::Iterator::collect::<::mlua::MultiValue>(__args.by_ref()) },
+                        ty.clone(),
+                        error,
+                    )
+                }
+                false => {
+                    tokens
+                        .conversion_bounds
+                        .push(quote! { #ty: ::mlua::FromLua });
+                    convert_value(next_value.clone(), ty.clone(), error)
+                }
+            };
+            tokens
+                .prelude
+                .push(quote! { let #binding: #ty = #converted; });
+        }
+    }
+
+    // The user's Lua argument is bound only after the conversions, since async functions take it by value
+    if let (Some(lua_arg), false) = (lua_binding, func.keep) {
+        let name = &lua_arg.name;
+        tokens.prelude.push(quote! { let #name = __lua; });
+    }
+
+    // Rewritten methods that take `self` by value get a clone of the userdata as well
+    if let (Some(SelfReceiver::Value { mutable }), false) = (func.receiver, func.keep) {
+        let mutability = match mutable {
+            true => quote! { mut },
+            false => TokenStream2::new(),
+        };
+        tokens
+            .prelude
+            .push(quote! { let #mutability this = ::std::clone::Clone::clone(this); });
+    }
+
+    for user_arg in user_args.iter() {
+        if let Some(default) = &user_arg.arg.default {
+            let (binding, value, ty) = (&user_arg.binding, &user_arg.value, &user_arg.arg.ty);
+            // Kept functions take their own argument names, while the rest bind the user's pattern
+            let pattern = match func.keep {
+                true => value.clone(),
+                false => user_arg.arg.name.to_token_stream(),
+            };
+            tokens.prelude.push(quote! {
+                let #pattern: #ty = ::std::option::Option::unwrap_or_else(#binding, || #default);
+            });
+        }
+    }
+
+    for user_arg in user_args.iter() {
+        if let Some(checks) = check_tokens(user_arg, lua_name, &location) {
+            tokens.prelude.push(checks);
+        }
+    }
+
+    tokens
+}
+
+/// The Rust location of the binding for error messages. It's only included with the `error-location` feature
+fn error_location(func: &ParsedFunc) -> TokenStream2 {
+    if cfg!(feature = "error-location") {
+        // The location macros are spanned to the function, so they point to it instead of the macro call
+        quote_spanned! { func.name.span() =>
+            ::std::option::Option::Some((::std::file!(), ::std::line!()))
+        }
+    } else {
+        quote! { ::std::option::Option::None }
+    }
+}
+
+/// Map a failed argument conversion into a bad argument error, which names the function (as it's seen from Lua)
+/// and the argument, and keeps the conversion error as its cause. The function is followed by the location of the
+/// binding, if there's one (check `mlua_bindgen::__private::bad_argument_target`)
+fn conversion_error(
+    lua_name: &str,
+    pos: usize,
+    name: Option<&str>,
+    location: &TokenStream2,
+) -> TokenStream2 {
+    let name = match name {
+        Some(name) => quote! { ::std::option::Option::Some(#name.to_owned()) },
+        None => quote! { ::std::option::Option::None },
+    };
+    quote! {
+        |err| ::mlua::Error::BadArgument {
+            to: ::std::option::Option::Some(::mlua_bindgen::__private::bad_argument_target(#lua_name, #location)),
+            pos: #pos,
+            name: #name,
+            cause: ::std::sync::Arc::new(err),
+        }
+    }
+}
+
+/// Convert a single Lua value into the argument type
+fn convert_value(value: TokenStream2, ty: TokenStream2, error: TokenStream2) -> TokenStream2 {
+    quote! {
+        ::std::result::Result::map_err(<#ty as ::mlua::FromLua>::from_lua(#value, &__lua), #error)?
+    }
+}
+
+/// Convert multiple Lua values into the (variadic) argument type
+fn convert_values(values: TokenStream2, ty: TokenStream2, error: TokenStream2) -> TokenStream2 {
+    quote! {
+        ::std::result::Result::map_err(<#ty as ::mlua::FromLuaMulti>::from_lua_multi(#values, &__lua), #error)?
+    }
+}
+
+/// Generate the argument checks. They run after all the arguments are converted, and return a bad argument
+/// error for the first failed check (named the same way as in [`conversion_error`]).
+fn check_tokens(
+    user_arg: &UserArg,
+    lua_name: &str,
+    location: &TokenStream2,
+) -> Option<TokenStream2> {
+    let arg = user_arg.arg;
+    if arg.checks.is_empty() {
+        return None;
+    }
+
+    let (pos, value, arg_name) = (user_arg.pos, &user_arg.value, user_arg.name());
+    let bad_argument = |message: TokenStream2| {
+        quote! {
+            return ::std::result::Result::Err(::mlua::Error::BadArgument {
+                to: ::std::option::Option::Some(::mlua_bindgen::__private::bad_argument_target(#lua_name, #location)),
+                pos: #pos,
+                name: ::std::option::Option::Some(#arg_name.to_owned()),
+                cause: ::std::sync::Arc::new(::mlua::Error::runtime(#message)),
+            })
+        }
+    };
+
+    let checks = arg.checks.iter().map(|check| {
+        let failed = bad_argument(check.describe().to_token_stream());
+        match check {
+            ArgCheck::Range(range) => quote! {
+                if !(#range).contains(__value) { #failed }
+            },
+            ArgCheck::Len { min, max } => {
+                let min = min.iter();
+                let max = max.iter();
+                quote! {
+                    let __len = __value.len();
+                    if #(__len < #min ||)* #(__len > #max ||)* false { #failed }
+                }
+            }
+            ArgCheck::NonEmpty => quote! {
+                if __value.is_empty() { #failed }
+            },
+            ArgCheck::Validate(path) => {
+                let failed = bad_argument(quote! { err.to_string() });
+                quote! {
+                    if let ::std::result::Result::Err(err) = #path(__value) { #failed }
+                }
+            }
+        }
+    });
+
+    // Optional arguments are only checked when present
+    Some(match is_option_ty(&arg.ty) {
+        true => quote! {
+            if let ::std::option::Option::Some(__value) = &#value { #(#checks)* }
+        },
+        false => quote! {
+            { let __value = &#value; #(#checks)* }
+        },
+    })
+}
@@ -1,6 +1,6 @@
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, ToTokens};
-use shared::items::enums::parse_enum;
+use shared::{items::enums::parse_enum, utils::ItemAttributes};
 use syn::ItemEnum;
 
 /// This will simply implement the [`mlua_bindgen::AsTable`] trait for the table, it doesn't overwrite
@@ -11,11 +11,15 @@ use syn::ItemEnum;
 /// And yes, I call them "structs", even though they are impl blocks just for simplicity.
 ///
 /// Currently these enums don't support discriminants, so all values start from 0.
-pub fn expand_enum(input: TokenStream2, item: ItemEnum) -> TokenStream2 {
-    let parsed_enum = match parse_enum(item) {
+pub fn expand_enum(attrs: ItemAttributes, input: TokenStream2, item: ItemEnum) -> TokenStream2 {
+    let mut parsed_enum = match parse_enum(item) {
         Ok(item) => item,
         Err(err) => return err.to_compile_error(),
     };
+    // Enums are only named by their modules, but their attributes are still checked here
+    if let Err(err) = parsed_enum.apply_attrs(attrs) {
+        return err.to_compile_error();
+    }
 
     let name = parsed_enum.ident.to_token_stream();
 
@@ -1,32 +1,136 @@
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, ToTokens};
-use shared::funcs::{parse_func, FuncKind};
+use shared::{
+    funcs::{parse_func, remove_arg_attrs, remove_func_attrs, FuncKind, ParsedFunc},
+    utils::{syn_error, ItemAttributes, NameRules},
+};
 use syn::ItemFn;
 
-use crate::utils::into_arg_tokens;
+use crate::args::{arg_tokens, ArgPack};
+use crate::utils::{
+    binding_where_clause, check_async, kept_call, lua_return_ty, rewritten_body, static_checks,
+};
 
 /// Expand functions. This will overwrite the original function and also add a static type check
 /// to ensure that it has proper arguments/return types (by mlua rules of course)
-pub fn expand_fn(input: ItemFn) -> TokenStream2 {
+///
+/// If the function is marked with `keep`, it's instead left untouched, while a separate Lua adapter
+/// is generated for it (check [`expand_kept_fn`])
+pub fn expand_fn(attrs: ItemAttributes, input: ItemFn) -> TokenStream2 {
+    let item = input.clone();
     let mut parsed = match parse_func(input, &FuncKind::Func) {
         Ok(parsed) => parsed,
         Err(err) => return err.to_compile_error(),
     };
+    if let Err(err) = parsed.apply_attrs(attrs) {
+        return err.to_compile_error();
+    }
+    if let Err(err) = check_async(&parsed) {
+        return err.to_compile_error();
+    }
+
+    // An unsafe function can't be safely called from Lua, so it's better to make the user wrap it themselves
+    if item.sig.unsafety.is_some() {
+        return syn_error(
+            item.sig.unsafety,
+            "Unsafe functions can't be exposed to Lua. Wrap it into a safe function instead",
+        )
+        .to_compile_error();
+    }
+
+    if parsed.keep {
+        return expand_kept_fn(item, parsed);
+    }
+
+    let mut fn_attrs = item.attrs;
+    remove_func_attrs(&mut fn_attrs);
+    let body = rewritten_body(&parsed);
+    let return_ty = lua_return_ty(&parsed.return_ty);
+    let name = &parsed.name;
+    let pub_param = &parsed.visibility;
+
+    // User arguments are taken as they were passed from Lua, and converted in the prelude
+    let lua_ty = &parsed.args[0].ty;
+    let args = arg_tokens(&parsed, &parsed.lua_name(NameRules::ITEM), ArgPack::Multi);
+    let body = args.with_prelude(body);
+    let (lua_param, user_args_name, user_args_ty) = (
+        &args.req_arg_names[0],
+        &args.user_args_name,
+        &args.user_args_ty,
+    );
+
+    // Generic functions keep their generics, and are only checked with their instantiations
+    let generics = &parsed.generics;
+    let where_clause = binding_where_clause(&parsed, &args.conversion_bounds);
+    let lint_allow = generic_lint_allow(&parsed);
+    let static_checks = static_checks(&parsed, name);
+
+    // Async functions take an owned `Lua` instead
+    let async_param = if parsed.is_async {
+        quote! { async }
+    } else {
+        TokenStream2::new()
+    };
+
+    quote! {
+        #(#fn_attrs)*
+        #lint_allow
+        #pub_param #async_param fn #name #generics(#lua_param: #lua_ty, #user_args_name: #user_args_ty) -> ::mlua::Result<#return_ty> #where_clause #body
+
+        #static_checks
+    }
+}
+
+/// Conversion bounds of generic bindings can repeat the bounds of their generic parameters
+fn generic_lint_allow(func: &ParsedFunc) -> TokenStream2 {
+    match func.is_generic() {
+        true => quote! { #[allow(clippy::multiple_bound_locations)] },
+        false => TokenStream2::new(),
+    }
+}
+
+/// Expand a kept function. The original function stays exactly as it was written (so it can still be called
+/// from Rust), while Lua gets a generated `{name}_lua` adapter, which has the usual mlua function signature.
+///
+/// Kept functions don't have to return `mlua::Result`: a plain `-> T` is always successful, while other result
+/// types have their errors converted by the adapter.
+fn expand_kept_fn(mut item: ItemFn, parsed: ParsedFunc) -> TokenStream2 {
+    remove_func_attrs(&mut item.attrs);
+    remove_arg_attrs(&mut item.sig.inputs);
+
+    let name = &parsed.name;
+    let lua_name = parsed.lua_ident();
+    let visibility = &parsed.visibility;
 
-    let name = parsed.name;
-    let block = parsed.block;
-    let return_ty = parsed.return_ty;
-    let pub_param = parsed.visibility;
+    let return_ty = lua_return_ty(&parsed.return_ty);
+    let kept = arg_tokens(&parsed, &parsed.lua_name(NameRules::ITEM), ArgPack::Multi);
+    let (lua_name_param, user_args_name, user_args_ty) = (
+        &kept.req_arg_names[0],
+        &kept.user_args_name,
+        &kept.user_args_ty,
+    );
+    let body = kept.with_prelude(kept_call(&parsed, name.to_token_stream(), &kept.call_args));
 
-    // The lua argument is added separately from user arguments, so we pop it and add it separately.
-    let lua_arg = parsed.args.remove(0).into_token_stream();
-    let (_, user_arg_names, user_arg_types) = into_arg_tokens(parsed.args);
+    let (async_param, lua_ty) = if parsed.is_async {
+        (quote! { async }, quote! { ::mlua::Lua })
+    } else {
+        (TokenStream2::new(), quote! { &::mlua::Lua })
+    };
+
+    // The adapter takes the generics of the original function
+    let generics = &parsed.generics;
+    let where_clause = binding_where_clause(&parsed, &kept.conversion_bounds);
+    let lint_allow = generic_lint_allow(&parsed);
+    let static_checks = static_checks(&parsed, &lua_name);
 
     quote! {
-        #pub_param fn #name(#lua_arg, (#(#user_arg_names), *): (#(#user_arg_types), *)) -> ::mlua::Result<#return_ty> #block
+        #item
+
+        #lint_allow
+        #visibility #async_param fn #lua_name #generics(#lua_name_param: #lua_ty, #user_args_name: #user_args_ty) -> ::mlua::Result<#return_ty> #where_clause {
+            #body
+        }
 
-        const _:fn(&::mlua::Lua) = |l| {
-            _ = l.create_function(#name);
-        };
+        #static_checks
     }
 }
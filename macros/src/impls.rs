@@ -1,129 +1,862 @@
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{quote, ToTokens};
 use shared::{
-    funcs::FuncKind,
-    impls::{parse_impl, FieldKind, ParsedField, ParsedImplFunc},
+    funcs::{remove_arg_attrs, remove_func_attrs, FuncKind, ParsedFunc},
+    impls::{
+        is_lua_impl_fn, parse_impl, FieldKind, ParsedBase, ParsedConstant, ParsedConstructor,
+        ParsedField, ParsedImplFunc, CONSTANT_ATTR, IMPL_FUNC_ATTRS,
+    },
+    utils::{contains_attr, ItemAttributes, NameRules, MLUA_BINDGEN_ATTR},
 };
-use syn::ItemImpl;
+use syn::{ImplItem, ItemImpl, Type};
 
-use crate::utils::into_arg_tokens;
+use crate::args::{arg_tokens, ArgPack, ArgTokens};
+use crate::utils::{
+    check_async, kept_call, lua_return_ty, part_id, rewritten_body, userdata_where_clause,
+};
+
+/// Parts of a registration closure: its argument tokens, body and the type it returns to Lua
+struct ClosureParts {
+    args: ArgTokens,
+    body: TokenStream2,
+    return_ty: TokenStream2,
+}
+
+/// Build the registration closure parts for an impl function. Rewritten functions paste their block into the
+/// closure, while kept functions are simply called through `Self`.
+///
+/// `lua_name` is the full name of the function in Lua (i.e. `Vector.new` or `Vector:len`), for error messages.
+/// `remote` is the foreign type of a remote impl block (see [`remote_body`]).
+fn closure_parts(
+    func: ParsedFunc,
+    lua_name: &str,
+    pack: ArgPack,
+    remote: Option<&Type>,
+) -> ClosureParts {
+    let args = arg_tokens(&func, lua_name, pack);
+    let body = if func.keep {
+        let name = &func.name;
+        kept_call(&func, quote! { Self::#name }, &args.call_args)
+    } else {
+        rewritten_body(&func)
+    };
+    let body = args.with_prelude(body);
+
+    let return_ty = lua_return_ty(&func.return_ty);
+    match remote.filter(|remote| is_remote_ty(return_ty, remote)) {
+        Some(remote) => ClosureParts {
+            args,
+            body: remote_body(body, remote, func.is_async),
+            return_ty: quote! { ::mlua::AnyUserData },
+        },
+        None => ClosureParts {
+            args,
+            body,
+            return_ty: return_ty.to_token_stream(),
+        },
+    }
+}
+
+/// Whether a type is the foreign type of a remote impl block. Macros can't resolve types, so paths are compared by
+/// their last segment (i.e. `Vec3` is the same type as `glam::Vec3`). `Self` is already replaced by the foreign type
+/// (see `ParsedImpl::remote`)
+fn is_remote_ty(ty: &Type, remote: &Type) -> bool {
+    fn last_segment(ty: &Type) -> Option<String> {
+        match ty {
+            Type::Path(ty_path) if ty_path.qself.is_none() => ty_path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.to_token_stream().to_string()),
+            Type::Group(group) => last_segment(&group.elem),
+            Type::Paren(paren) => last_segment(&paren.elem),
+            _ => None,
+        }
+    }
+    match (last_segment(ty), last_segment(remote)) {
+        (Some(ty), Some(remote)) => ty == remote,
+        _ => ty.to_token_stream().to_string() == remote.to_token_stream().to_string(),
+    }
+}
+
+/// Wrap the body of a binding that returns the foreign type of its remote impl block. mlua can only convert
+/// `UserData` types by themselves, so the returned value is turned into userdata of the registered type instead.
+///
+/// The body is evaluated separately, so its `return` and `?` expressions keep working. It's usually a block already,
+/// hence the allowed braces.
+fn remote_body(body: TokenStream2, remote: &Type, is_async: bool) -> TokenStream2 {
+    match is_async {
+        true => quote! {
+            {
+                let __remote_lua = ::std::clone::Clone::clone(&__lua);
+                #[allow(unused_braces)]
+                let __value = ::mlua_bindgen::__private::typed_future::<::mlua::Result<#remote>, _>(
+                    async move { #body }
+                ).await?;
+                __remote_lua.create_any_userdata(__value)
+            }
+        },
+        false => quote! {
+            {
+                #[allow(unused_braces)]
+                let __value = (move || -> ::mlua::Result<#remote> { #body })()?;
+                __lua.create_any_userdata(__value)
+            }
+        },
+    }
+}
+
+/// The value of an exported constant. Constants of the foreign type of a remote impl block are turned into userdata
+/// of the registered type, just like the values its bindings return (see [`remote_body`])
+fn const_value(
+    constant: &ParsedConstant,
+    lua: TokenStream2,
+    remote: Option<&Type>,
+) -> TokenStream2 {
+    let ident = &constant.ident;
+    match remote.filter(|remote| is_remote_ty(&constant.ty, remote)) {
+        Some(_) => quote! { #lua.create_any_userdata(Self::#ident)? },
+        None => quote! { Self::#ident },
+    }
+}
 
 /// This will parse the supplied impl function (and its [`FieldKind`]), extract neccessary information,
 /// then transform into a field registration code for mlua.
-pub fn expand_field(input: ParsedField) -> TokenStream2 {
+///
+/// `rules` are the naming rules of the impl block (see `ParsedImpl::member_rules`), and `remote` is its foreign type,
+/// if it's a remote impl block
+pub fn expand_field(
+    type_name: &str,
+    input: ParsedField,
+    rules: NameRules,
+    remote: Option<&Type>,
+) -> TokenStream2 {
     let (func, kind) = (input.func, input.kind);
 
-    let name = &func.name;
-    let block = &func.block;
-    let return_ty = &func.return_ty;
+    // Meta fields keep their names, just like meta functions
+    let name = match kind {
+        FieldKind::Meta => func.lua_name(NameRules::MEMBER),
+        _ => func.lua_name(rules),
+    };
+
+    // Static and meta fields are converted while the type is registered, so their values can't be userdata of
+    // the remote type itself
+    let remote = remote.filter(|_| !matches!(kind, FieldKind::Static | FieldKind::Meta));
 
-    let (req_arg_names, user_arg_names, user_arg_types) = into_arg_tokens(func.args);
+    // Setters receive a single value
+    let lua_name = format!("{type_name}.{name}");
+    let ClosureParts {
+        args,
+        body,
+        return_ty,
+    } = closure_parts(func, &lua_name, ArgPack::Single, remote);
+    let ArgTokens {
+        req_arg_names,
+        user_args_name,
+        user_args_ty,
+        ..
+    } = args;
 
     // It could be concatenated, but I'll probably leave it for readability reasons.
     match kind {
         FieldKind::Getter => quote! {
             fields.add_field_method_get::<_, #return_ty>(
-                stringify!(#name),
-                |#(#req_arg_names), *| #block
+                #name,
+                |#(#req_arg_names), *| #body
             );
         },
         FieldKind::Setter => quote! {
-            fields.add_field_method_set::<_, (#(#user_arg_types), *)>(
-                stringify!(#name),
-                |#(#req_arg_names), *, (#(#user_arg_names), *)| #block
+            fields.add_field_method_set::<_, #user_args_ty>(
+                #name,
+                |#(#req_arg_names), *, #user_args_name| #body
+            );
+        },
+        FieldKind::FunctionGetter => quote! {
+            fields.add_field_function_get::<_, #return_ty>(
+                #name,
+                |#(#req_arg_names), *| #body
+            );
+        },
+        FieldKind::FunctionSetter => quote! {
+            fields.add_field_function_set::<_, #user_args_ty>(
+                #name,
+                |#(#req_arg_names), *, #user_args_name| #body
             );
         },
+        FieldKind::Meta => quote! {
+            fields.add_meta_field_with::<_, #return_ty>(
+                #name,
+                |#(#req_arg_names), *| #body
+            );
+        },
+        // `add_field` takes the value itself, so it's wrapped into a value that's computed when mlua converts it.
+        // This way static fields can use the Lua state, and their errors are raised when the type is registered.
+        FieldKind::Static => quote! {
+            {
+                struct StaticField<F>(F);
+
+                impl<F, R> ::mlua::IntoLua for StaticField<F>
+                where
+                    F: ::std::ops::FnOnce(&::mlua::Lua) -> ::mlua::Result<R>,
+                    R: ::mlua::IntoLua,
+                {
+                    fn into_lua(self, lua: &::mlua::Lua) -> ::mlua::Result<::mlua::Value> {
+                        ::mlua::IntoLua::into_lua((self.0)(lua)?, lua)
+                    }
+                }
+
+                fields.add_field(
+                    #name,
+                    StaticField(|#(#req_arg_names)*: &::mlua::Lua| -> ::mlua::Result<#return_ty> { #body })
+                );
+            }
+        },
     }
 }
 
-pub fn expand_impl_func(input: ParsedImplFunc) -> TokenStream2 {
+pub fn expand_impl_func(
+    type_name: &str,
+    input: ParsedImplFunc,
+    rules: NameRules,
+    remote: Option<&Type>,
+) -> TokenStream2 {
     let (func, kind) = (input.func, input.kind);
 
-    let name = &func.name;
-    let block = &func.block;
-    let return_ty = &func.return_ty;
+    let name = func.lua_name(rules);
+    let is_async = func.is_async;
 
-    let (req_arg_names, user_arg_names, user_arg_types) = into_arg_tokens(func.args);
+    // Methods are called with a colon in Lua
+    let lua_name = match kind {
+        FuncKind::Method | FuncKind::MethodMut | FuncKind::MetaMethod | FuncKind::MetaMethodMut => {
+            format!("{type_name}:{name}")
+        }
+        _ => format!("{type_name}.{name}"),
+    };
+    let ClosureParts {
+        args,
+        body,
+        return_ty,
+    } = closure_parts(func, &lua_name, ArgPack::Multi, remote);
+    let ArgTokens {
+        req_arg_names,
+        user_args_name,
+        user_args_ty,
+        ..
+    } = args;
 
+    // Async closures return a future instead of the result directly, so the async mlua API
+    // also takes an additional generic argument for the future type.
+    let generics = if is_async {
+        quote! { _, #user_args_ty, _, #return_ty }
+    } else {
+        quote! { _, #user_args_ty, #return_ty }
+    };
+    let closure = match (&kind, is_async) {
+        // Async methods receive owned userdata references instead of `&Self`/`&mut Self`, so we borrow
+        // them inside the future to keep the same `this` semantics as in synchronous methods.
+        (FuncKind::Method | FuncKind::MetaMethod, true) => {
+            let (lua_param, this_name) = (&req_arg_names[0], &req_arg_names[1]);
+            quote! {
+                |#lua_param, this_ref, #user_args_name| async move {
+                    let #this_name = &*this_ref;
+                    #body
+                }
+            }
+        }
+        (FuncKind::MethodMut | FuncKind::MetaMethodMut, true) => {
+            let (lua_param, this_name) = (&req_arg_names[0], &req_arg_names[1]);
+            quote! {
+                |#lua_param, mut this_ref, #user_args_name| async move {
+                    let #this_name = &mut *this_ref;
+                    #body
+                }
+            }
+        }
+        (_, true) => {
+            quote! { |#(#req_arg_names), *, #user_args_name| async move { #body } }
+        }
+        (_, false) => quote! { |#(#req_arg_names), *, #user_args_name| #body },
+    };
     // It could be concatenated, but I'll probably leave it for readability reasons.
-    match kind {
-        FuncKind::Func => quote! {
+    match (kind, is_async) {
+        (FuncKind::Func, false) => quote! {
             table.set(
-                stringify!(#name),
-                lua.create_function::<_, (#(#user_arg_types),*), #return_ty>(
-                    |#(#req_arg_names), *, (#(#user_arg_names), *)| #block
-                )?
+                #name,
+                lua.create_function::<#generics>(#closure)?
             )?;
         },
-        FuncKind::Method => quote! {
-            methods.add_method::<_, (#(#user_arg_types),*), #return_ty>(
-                stringify!(#name),
-                |#(#req_arg_names), *, (#(#user_arg_names), *)| #block
-            );
+        (FuncKind::Func, true) => quote! {
+            table.set(
+                #name,
+                lua.create_async_function::<#generics>(#closure)?
+            )?;
         },
-        FuncKind::MethodMut => quote! {
-            methods.add_method_mut::<_, (#(#user_arg_types), *), #return_ty>(
-                stringify!(#name),
-                |#(#req_arg_names), *, (#(#user_arg_names), *)| #block
-            );
+        (FuncKind::Method, false) => quote! {
+            methods.add_method::<#generics>(#name, #closure);
         },
-        FuncKind::Meta => quote! {
-            methods.add_meta_function::<_, (#(#user_arg_types),*), #return_ty>(
-                stringify!(#name),
-                |#(#req_arg_names), *, (#(#user_arg_names), *)| #block
-            );
+        (FuncKind::Method, true) => quote! {
+            methods.add_async_method::<#generics>(#name, #closure);
+        },
+        (FuncKind::MethodMut, false) => quote! {
+            methods.add_method_mut::<#generics>(#name, #closure);
+        },
+        (FuncKind::MethodMut, true) => quote! {
+            methods.add_async_method_mut::<#generics>(#name, #closure);
+        },
+        (FuncKind::Function, false) => quote! {
+            methods.add_function::<#generics>(#name, #closure);
+        },
+        (FuncKind::Function, true) => quote! {
+            methods.add_async_function::<#generics>(#name, #closure);
+        },
+        (FuncKind::FunctionMut, false) => quote! {
+            methods.add_function_mut::<#generics>(#name, #closure);
+        },
+        // Mutable functions can't be async (see `parse_impl_func`)
+        (FuncKind::FunctionMut, true) => unreachable!(),
+        (FuncKind::Meta, false) => quote! {
+            methods.add_meta_function::<#generics>(#name, #closure);
+        },
+        (FuncKind::Meta, true) => quote! {
+            methods.add_async_meta_function::<#generics>(#name, #closure);
+        },
+        (FuncKind::MetaMethod, false) => quote! {
+            methods.add_meta_method::<#generics>(#name, #closure);
+        },
+        (FuncKind::MetaMethod, true) => quote! {
+            methods.add_async_meta_method::<#generics>(#name, #closure);
+        },
+        (FuncKind::MetaMethodMut, false) => quote! {
+            methods.add_meta_method_mut::<#generics>(#name, #closure);
+        },
+        (FuncKind::MetaMethodMut, true) => quote! {
+            methods.add_async_meta_method_mut::<#generics>(#name, #closure);
         },
     }
 }
 
-/// Expand the impl block. This will overwrite the entire impl block
-/// with an implementation of [`mlua::UserData`] + [`mlua_bindgen::AsTable`]
-pub fn expand_impl(input: ItemImpl) -> TokenStream2 {
-    let parsed_impl = match parse_impl(input) {
+/// Expand the constructor of a type into the `__call` metamethod of its class, so calling the class
+/// (i.e. `Vector(1, 2)`) calls the constructor. Lua passes the class itself first, which is skipped.
+///
+/// With `proxy`, the metamethod is registered on the userdata type itself (see `ParsedImpl::as_table`), otherwise
+/// it's set to the metatable of the class table. Instances share the metamethod with the proxy, so calling them is
+/// an error.
+///
+/// Constructors with the table form (`#[constructor(table)]`) take their arguments from a table when they're called
+/// with a single one (i.e. `Vector{ x = 1, y = 2 }`).
+pub fn expand_constructor(
+    type_name: &str,
+    input: ParsedConstructor,
+    proxy: bool,
+    remote: Option<&Type>,
+) -> TokenStream2 {
+    let func = input.func;
+    let is_async = func.is_async;
+
+    let table_body = match input.table_form {
+        true => {
+            let mut table_func = func.clone();
+            table_func.table_args = true;
+            Some(closure_parts(table_func, type_name, ArgPack::Multi, remote).body)
+        }
+        false => None,
+    };
+    let ClosureParts {
+        args,
+        body,
+        return_ty,
+    } = closure_parts(func, type_name, ArgPack::Multi, remote);
+    let req_arg_names = args.req_arg_names;
+    let body = match table_body {
+        Some(table_body) => quote! {
+            if __args.len() == 1 && ::std::matches!(__args.front(), ::std::option::Option::Some(::mlua::Value::Table(_))) {
+                #table_body
+            } else {
+                #body
+            }
+        },
+        None => body,
+    };
+
+    let args_ty = quote! { (::mlua::Value, ::mlua::MultiValue) };
+    if proxy {
+        let proxy_args_ty = quote! { (::mlua::AnyUserData, ::mlua::MultiValue) };
+        let instance_err = format!("attempt to call an instance of '{type_name}'");
+        let check_proxy = quote! {
+            if __class.is::<Self>() {
+                return Err(::mlua::Error::runtime(#instance_err));
+            }
+        };
+        return match is_async {
+            true => quote! {
+                methods.add_async_meta_function::<_, #proxy_args_ty, _, #return_ty>(
+                    "__call",
+                    |#(#req_arg_names), *, (__class, __args)| async move {
+                        #check_proxy
+                        #body
+                    }
+                );
+            },
+            false => quote! {
+                methods.add_meta_function::<_, #proxy_args_ty, #return_ty>(
+                    "__call",
+                    |#(#req_arg_names), *, (__class, __args)| {
+                        #check_proxy
+                        #body
+                    }
+                );
+            },
+        };
+    }
+    let create_fn = match is_async {
+        true => quote! {
+            lua.create_async_function::<_, #args_ty, _, #return_ty>(
+                |#(#req_arg_names), *, (_, __args)| async move { #body }
+            )?
+        },
+        false => quote! {
+            lua.create_function::<_, #args_ty, #return_ty>(
+                |#(#req_arg_names), *, (_, __args)| #body
+            )?
+        },
+    };
+    quote! {
+        let __class_meta = lua.create_table()?;
+        __class_meta.set("__call", #create_fn)?;
+        table.set_metatable(::std::option::Option::Some(__class_meta));
+    }
+}
+
+/// Expand the base of an extended type (`extends = Base, via = accessor, via_mut = accessor_mut`) into the `__index`
+/// and `__newindex` metamethods of its userdata. mlua only calls them for keys the type doesn't declare itself, so
+/// these are looked up on the base instead.
+///
+/// The base is borrowed from the userdata through the accessors, and passed to Lua as a scoped userdata of its own
+/// type, which is only valid while the lookup runs. That's why base methods are wrapped: the wrapper borrows the base
+/// mutably when it's called, and calls the method with it instead of the userdata itself. Wrappers are cached per base
+/// method, so looking the same method up again returns the same function.
+///
+/// The class proxy of the type shares its metatable, but has no base: unknown keys are `nil` on it, and setting them
+/// is an error.
+pub fn expand_base(base: &ParsedBase) -> TokenStream2 {
+    let (via, via_mut) = (&base.via, &base.via_mut);
+    let index = quote! {
+        __ud.borrow_scoped::<Self, _>(|__this| {
+            __lua.scope(|__scope| {
+                let __base = __scope.create_userdata_ref(#via(__this))?;
+                ::mlua::ObjectLike::get::<::mlua::Value>(&__base, __key)
+            })
+        })?
+    };
+    let with_base_mut = |body: TokenStream2| {
+        quote! {
+            __ud.borrow_mut_scoped::<Self, _>(|__this| {
+                __lua.scope(|__scope| {
+                    let __base = __scope.create_userdata_ref_mut(#via_mut(__this))?;
+                    #body
+                })
+            })?
+        }
+    };
+    let call = with_base_mut(quote! {
+        __method.call::<::mlua::MultiValue>((__base, __args))
+    });
+    let new_index = with_base_mut(quote! {
+        ::mlua::ObjectLike::set(&__base, __key, __value)
+    });
+
+    quote! {
+        // Closures are created for each Lua state, so is the cache of the wrappers
+        let __wrappers = ::std::sync::OnceLock::<::mlua::RegistryKey>::new();
+        methods.add_meta_function::<_, (::mlua::AnyUserData, ::mlua::Value), ::mlua::Value>(
+            "__index",
+            move |__lua, (__ud, __key)| {
+                if !__ud.is::<Self>() {
+                    return Ok(::mlua::Value::Nil);
+                }
+                let __method = match #index? {
+                    ::mlua::Value::Function(__method) => __method,
+                    __value => return Ok(__value),
+                };
+                let __wrappers = match __wrappers.get() {
+                    Some(__wrappers) => __wrappers,
+                    None => {
+                        let __table = __lua.create_registry_value(__lua.create_table()?)?;
+                        __wrappers.get_or_init(|| __table)
+                    }
+                };
+                let __wrappers = __lua.registry_value::<::mlua::Table>(__wrappers)?;
+                if let Some(__wrapper) = __wrappers.raw_get::<Option<::mlua::Function>>(&__method)? {
+                    return Ok(::mlua::Value::Function(__wrapper));
+                }
+                let __wrapper = __lua.create_function::<_, (::mlua::AnyUserData, ::mlua::MultiValue), ::mlua::MultiValue>({
+                    let __method = __method.clone();
+                    move |__lua, (__ud, __args)| #call
+                })?;
+                __wrappers.raw_set(__method, &__wrapper)?;
+                Ok(::mlua::Value::Function(__wrapper))
+            },
+        );
+        methods.add_meta_function::<_, (::mlua::AnyUserData, ::mlua::Value, ::mlua::Value), ()>(
+            "__newindex",
+            |__lua, (__ud, __key, __value)| {
+                if !__ud.is::<Self>() {
+                    let __key = __key.to_string()?;
+                    return Err(::mlua::Error::runtime(format!("attempt to set an unknown field '{__key}'")));
+                }
+                #new_index
+            },
+        );
+    }
+}
+
+/// Expand the impl block. This will implement [`mlua::UserData`] + [`mlua_bindgen::AsTable`] for the type (or generate
+/// `register` for remote types), and re-emit the original impl block with the items that aren't exported to Lua
+/// (check [`rust_impl_items`])
+pub fn expand_impl(attrs: ItemAttributes, input: ItemImpl) -> TokenStream2 {
+    let impl_attrs = input.attrs.clone();
+    let original = input.clone();
+    let mut parsed_impl = match parse_impl(input) {
         Ok(parsed) => parsed,
         Err(err) => return err.into_compile_error(),
     };
+    if let Err(err) = parsed_impl.apply_attrs(attrs) {
+        return err.into_compile_error();
+    }
+    let rust_items = rust_impl_items(original, parsed_impl.keep);
+    let type_name = parsed_impl.lua_name(NameRules::ITEM);
+    let member_rules = parsed_impl.member_rules();
     let impl_name = parsed_impl.name;
+    let remote = parsed_impl.remote.as_deref();
+    // Generic impls implement `UserData` for all of their type parameters
+    let params = parsed_impl.generics.params;
+    let where_clause = userdata_where_clause(&parsed_impl.generics.where_clause, &params);
+
+    let all_funcs = parsed_impl
+        .funcs
+        .iter()
+        .chain(parsed_impl.methods.iter())
+        .chain(parsed_impl.meta_funcs.iter());
+    let constructor_func = parsed_impl
+        .constructor
+        .iter()
+        .map(|constructor| &constructor.func);
+    for func in all_funcs
+        .map(|impl_func| &impl_func.func)
+        .chain(constructor_func)
+    {
+        if let Err(err) = check_async(func) {
+            return err.into_compile_error();
+        }
+    }
+
+    // Constants are set on the class table, and the ones marked with `field` are fields of the instances as well.
+    // Their values are created on access, since converting a constant of the type itself (i.e. `ZERO: Self`) while
+    // the type is being registered would register it again.
+    let const_field = |constant: &ParsedConstant| {
+        let name = constant.lua_name();
+        let value = const_value(constant, quote! { __lua }, remote);
+        quote! { fields.add_field_function_get(#name, |__lua, _| Ok(#value)); }
+    };
+    let const_fields = parsed_impl
+        .constants
+        .iter()
+        .filter(|constant| constant.field)
+        .map(const_field);
+    let const_values = parsed_impl.constants.iter().map(|constant| {
+        let name = constant.lua_name();
+        let value = const_value(constant, quote! { lua }, remote);
+        quote! { table.set(#name, #value)?; }
+    });
 
-    let fields: Vec<TokenStream2> = parsed_impl.fields.into_iter().map(expand_field).collect();
+    let fields: Vec<TokenStream2> = const_fields
+        .chain(
+            parsed_impl
+                .fields
+                .into_iter()
+                .map(|field| expand_field(&type_name, field, member_rules, remote)),
+        )
+        .collect();
+
+    let funcs: Vec<TokenStream2> = const_values
+        .chain(
+            parsed_impl
+                .funcs
+                .iter()
+                .map(|func| expand_impl_func(&type_name, func.clone(), member_rules, remote)),
+        )
+        .collect();
 
-    let funcs: Vec<TokenStream2> = parsed_impl
+    // Proxies have the class items registered on the type itself: functions are userdata functions, and constants
+    // are fields (unless they're already)
+    let class_fields: Vec<TokenStream2> = parsed_impl
+        .constants
+        .iter()
+        .filter(|constant| !constant.field)
+        .map(const_field)
+        .collect();
+    let class_methods: Vec<TokenStream2> = parsed_impl
         .funcs
         .into_iter()
-        .map(expand_impl_func)
+        .map(|func| {
+            let func = ParsedImplFunc {
+                kind: FuncKind::Function,
+                ..func
+            };
+            expand_impl_func(&type_name, func, member_rules, remote)
+        })
         .collect();
 
     let methods: Vec<TokenStream2> = parsed_impl
         .methods
         .into_iter()
-        .map(expand_impl_func)
+        .map(|func| expand_impl_func(&type_name, func, member_rules, remote))
         .collect();
 
+    // Meta functions keep their names, since Lua looks them up by their exact names
     let meta_funcs: Vec<TokenStream2> = parsed_impl
         .meta_funcs
         .into_iter()
-        .map(expand_impl_func)
+        .map(|func| expand_impl_func(&type_name, func, NameRules::MEMBER, remote))
         .collect();
 
-    quote! {
-        impl ::mlua::UserData for #impl_name {
-            fn add_fields<F: ::mlua::UserDataFields<Self>>(fields: &mut F) {
-                #(#fields)*
+    let (constructor, proxy_constructor) = parsed_impl
+        .constructor
+        .map(|constructor| {
+            (
+                expand_constructor(&type_name, (*constructor).clone(), false, remote),
+                expand_constructor(&type_name, *constructor, true, remote),
+            )
+        })
+        .unzip();
+    let base = parsed_impl.base.as_deref().map(expand_base);
+
+    if let Some(part) = &parsed_impl.partial {
+        let id = part_id(part);
+        return quote! {
+            #(#impl_attrs)*
+            impl<'a, F: ::mlua::UserDataFields<Self>, #params>
+                ::mlua_bindgen::__private::UserDataPart<::mlua_bindgen::__private::FieldsPart<'a, F>, #id>
+                for #impl_name #where_clause
+            {
+                // Parts don't have to register every kind of item
+                #[allow(unused_variables)]
+                fn register_part(part: ::mlua_bindgen::__private::FieldsPart<'a, F>) {
+                    let fields = part.0;
+                    #(#fields)*
+                }
+            }
+
+            #(#impl_attrs)*
+            impl<'a, M: ::mlua::UserDataMethods<Self>, #params>
+                ::mlua_bindgen::__private::UserDataPart<::mlua_bindgen::__private::MethodsPart<'a, M>, #id>
+                for #impl_name #where_clause
+            {
+                #[allow(unused_variables)]
+                fn register_part(part: ::mlua_bindgen::__private::MethodsPart<'a, M>) {
+                    let methods = part.0;
+                    #(#methods)*
+                    #(#meta_funcs)*
+                }
             }
 
-            fn add_methods<M: ::mlua::UserDataMethods<Self>>(methods: &mut M) {
-                #(#methods)*
-                #(#meta_funcs)*
+            #(#impl_attrs)*
+            impl<'a, F: ::mlua::UserDataFields<Self>, #params>
+                ::mlua_bindgen::__private::UserDataPart<::mlua_bindgen::__private::ClassFieldsPart<'a, F>, #id>
+                for #impl_name #where_clause
+            {
+                #[allow(unused_variables)]
+                fn register_part(part: ::mlua_bindgen::__private::ClassFieldsPart<'a, F>) {
+                    let fields = part.0;
+                    #(#class_fields)*
+                }
             }
+
+            #(#impl_attrs)*
+            impl<'a, M: ::mlua::UserDataMethods<Self>, #params>
+                ::mlua_bindgen::__private::UserDataPart<::mlua_bindgen::__private::ClassMethodsPart<'a, M>, #id>
+                for #impl_name #where_clause
+            {
+                #[allow(unused_variables)]
+                fn register_part(part: ::mlua_bindgen::__private::ClassMethodsPart<'a, M>) {
+                    let methods = part.0;
+                    #(#class_methods)*
+                }
+            }
+
+            #(#impl_attrs)*
+            impl<'a, #params>
+                ::mlua_bindgen::__private::UserDataPart<
+                    ::mlua_bindgen::__private::FuncsPart<'a, ::mlua::Lua, ::mlua::Table, ::mlua::Error>,
+                    #id,
+                >
+                for #impl_name #where_clause
+            {
+                #[allow(unused_variables)]
+                fn register_part(
+                    part: ::mlua_bindgen::__private::FuncsPart<'a, ::mlua::Lua, ::mlua::Table, ::mlua::Error>,
+                ) -> ::mlua::Result<()> {
+                    let (lua, table) = (part.lua, part.table);
+                    #(#funcs)*
+                    Ok(())
+                }
+            }
+
+            #(#impl_attrs)*
+            impl<#params> #impl_name #where_clause {
+                #(#rust_items)*
+            }
+        };
+    }
+
+    // Parts of the type are registered along with its own items. Each part is looked up by its id, so parts
+    // that were disabled (i.e. with `#[cfg(...)]`) are simply skipped
+    let part_ids: Vec<u64> = parsed_impl.parts.iter().map(part_id).collect();
+    let part_lookup = match part_ids.is_empty() {
+        true => TokenStream2::new(),
+        false => {
+            quote! { use ::mlua_bindgen::__private::{NoUserDataPart as _, ViaUserDataPart as _}; }
         }
+    };
+
+    // Types exported as tables keep their class items in the table alone
+    let (class_fields, class_methods) = match parsed_impl.as_table {
+        true => (TokenStream2::new(), TokenStream2::new()),
+        false => (
+            quote! {
+                #(#class_fields)*
+                #(
+                    (&::mlua_bindgen::__private::PartOf::<Self, #part_ids>::new())
+                        .register_part(::mlua_bindgen::__private::ClassFieldsPart(fields));
+                )*
+            },
+            quote! {
+                #(#class_methods)*
+                #proxy_constructor
+                #(
+                    (&::mlua_bindgen::__private::PartOf::<Self, #part_ids>::new())
+                        .register_part(::mlua_bindgen::__private::ClassMethodsPart(methods));
+                )*
+            },
+        ),
+    };
+
+    // Remote types can't implement `UserData` (because of orphan rules), so their items are registered on the
+    // foreign type by `register` instead. They can't be extended or split into parts (see `ParsedImpl::remote`)
+    let (userdata_impl, register) = match remote {
+        Some(remote) => (
+            TokenStream2::new(),
+            quote! {
+                pub fn register(lua: &::mlua::Lua) -> ::mlua::Result<()> {
+                    lua.register_userdata_type::<#remote>(|registry| {
+                        use ::mlua::{UserDataFields as _, UserDataMethods as _};
+                        #[allow(unused_variables)]
+                        let fields = &mut *registry;
+                        #(#fields)*
+                        #[allow(unused_variables)]
+                        let methods = registry;
+                        #(#methods)*
+                        #(#meta_funcs)*
+                    })
+                }
+            },
+        ),
+        None => (
+            quote! {
+                #(#impl_attrs)*
+                impl<#params> ::mlua::UserData for #impl_name #where_clause {
+                    fn add_fields<F: ::mlua::UserDataFields<Self>>(fields: &mut F) {
+                        // Fields declared on the struct itself, if it's a `#[mlua_bindgen]` struct
+                        {
+                            use ::mlua_bindgen::__private::{NoStructFields as _, ViaStructFields as _};
+                            (&::mlua_bindgen::__private::StructFieldsOf::<Self>::new()).add_struct_fields(fields);
+                        }
+                        #(#fields)*
+                        #part_lookup
+                        #(
+                            (&::mlua_bindgen::__private::PartOf::<Self, #part_ids>::new())
+                                .register_part(::mlua_bindgen::__private::FieldsPart(fields));
+                        )*
+                        #class_fields
+                    }
+
+                    fn add_methods<M: ::mlua::UserDataMethods<Self>>(methods: &mut M) {
+                        #(#methods)*
+                        #(#meta_funcs)*
+                        #base
+                        #part_lookup
+                        #(
+                            (&::mlua_bindgen::__private::PartOf::<Self, #part_ids>::new())
+                                .register_part(::mlua_bindgen::__private::MethodsPart(methods));
+                        )*
+                        #class_methods
+                    }
+                }
+            },
+            TokenStream2::new(),
+        ),
+    };
+
+    // The impl's own attributes (i.e. `#[cfg(...)]`) apply to everything generated from it
+    quote! {
+        #userdata_impl
+
+        #(#impl_attrs)*
+        impl<#params> #impl_name #where_clause {
+            #(#rust_items)*
+
+            #register
 
-        impl #impl_name {
             // AsTable
             pub fn as_table(lua: &::mlua::Lua) -> ::mlua::Result<::mlua::Table> {
                 let table = lua.create_table()?;
                 #(#funcs)*
+                #part_lookup
+                #(
+                    (&::mlua_bindgen::__private::PartOf::<Self, #part_ids>::new()).register_part(
+                        ::mlua_bindgen::__private::FuncsPart::<_, _, ::mlua::Error>::new(lua, &table),
+                    )?;
+                )*
+                #constructor
                 Ok(table)
             }
         }
     }
 }
+
+/// Collect the items of the original impl block that stay in Rust: plain functions, constants and any other
+/// items without Lua attributes are kept as they were written.
+///
+/// Lua functions are rewritten into registration closures, unless the impl is marked with `keep`. Kept impls
+/// re-emit them without their Lua attributes (i.e. `#[method]`, `#[lua_return("...")]` or
+/// `#[mlua_bindgen(table_args)]`), so they stay callable from Rust.
+fn rust_impl_items(input: ItemImpl, keep: bool) -> Vec<TokenStream2> {
+    input
+        .items
+        .into_iter()
+        .filter_map(|impl_item| match impl_item {
+            ImplItem::Fn(mut impl_fn) if is_lua_impl_fn(&impl_fn) => {
+                if !keep {
+                    return None;
+                }
+                impl_fn.attrs.retain(|attr| {
+                    !IMPL_FUNC_ATTRS
+                        .iter()
+                        .any(|name| attr.path().is_ident(name))
+                        && !attr.path().is_ident(MLUA_BINDGEN_ATTR)
+                });
+                remove_func_attrs(&mut impl_fn.attrs);
+                remove_arg_attrs(&mut impl_fn.sig.inputs);
+                Some(quote! { #impl_fn })
+            }
+            // Exported constants stay in Rust as well, only without their Lua attributes
+            ImplItem::Const(mut impl_const) if contains_attr(&impl_const.attrs, CONSTANT_ATTR) => {
+                impl_const.attrs.retain(|attr| {
+                    !attr.path().is_ident(CONSTANT_ATTR) && !attr.path().is_ident(MLUA_BINDGEN_ATTR)
+                });
+                Some(quote! { #impl_const })
+            }
+            impl_item => Some(quote! { #impl_item }),
+        })
+        .collect()
+}
@@ -1,18 +1,20 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use shared::utils::{parse_attributes, parse_item, syn_error, ItemKind};
-use syn::Item;
 
+mod args;
 mod enums;
 mod funcs;
 mod impls;
 mod mods;
+mod structs;
 mod utils;
 
 use enums::expand_enum;
 use funcs::expand_fn;
 use impls::expand_impl;
 use mods::expand_mod;
+use structs::expand_struct;
 
 /// # mlua_bindgen
 /// A generative attribute macro and also bindgen marker that can transform rust items (like impl blocks/functions) into mlua acceptible structures.
@@ -20,6 +22,7 @@ use mods::expand_mod;
 ///
 /// ## An example:
 /// ```
+/// # use mlua::Lua;
 /// struct MyStruct {
 ///     field: u32
 /// }
@@ -31,10 +34,12 @@ use mods::expand_mod;
 ///
 ///     fn add_methods<M: mlua::UserDataMethods<Self>>(methods: &mut M) {}
 /// }
-///```
+/// ```
 ///
 /// With this macro can also be expressed as:
 /// ```
+/// # use mlua::Lua;
+/// # use mlua_bindgen::mlua_bindgen;
 /// struct MyStruct {
 ///     field: u32
 /// }
@@ -43,13 +48,13 @@ use mods::expand_mod;
 /// impl MyStruct {
 ///     #[get]
 ///     fn field(_: &Lua, this: &Self) -> u32 {
-///         Ok(this.field)
+///         this.field
 ///     }
 ///
 ///     #[set]
-///     fn field(_: &Lua, this: &mut Self, new_val: u32) {
+///     #[mlua_bindgen(name = "field")]
+///     fn set_field(_: &Lua, this: &mut Self, new_val: u32) {
 ///         this.field = new_val;
-///         Ok(())
 ///     }
 /// }
 /// ```
@@ -58,55 +63,51 @@ use mods::expand_mod;
 ///
 /// ### Functions
 /// ```
+/// # use mlua_bindgen::mlua_bindgen;
 /// #[mlua_bindgen]
 /// fn cool(_: &mlua::Lua, sm: u32, hi: bool) -> u32 {
-///    Ok(50)fn has_bindgen_attr(attrs: &[syn::Attribute]) -> bool {
-///     for attr in attrs {
-///         if attr.path().is_ident("mlua_bindgen") {
-///             return true
-///         }
-///     }
-///     false
+///    50
 /// }
 /// ```
 /// ### UserData
 /// ```
+/// # use mlua::{AnyUserData, Lua};
+/// # use mlua_bindgen::mlua_bindgen;
+/// # struct MyType {
+/// #     x: f32,
+/// #     name: String,
+/// # }
 /// #[mlua_bindgen]
 /// impl MyType {
 ///     #[get]
-///    fn x(_: _, this: &Self) -> f32 {
-///        Ok(this.x)
+///    fn x(_: &Lua, this: &Self) -> f32 {
+///        this.x
 ///    }
 ///
 ///    #[set]
-///    fn x(_: _, this: &mut Self, to: f32) {
+///    #[mlua_bindgen(name = "x")]
+///    fn set_x(_: &Lua, this: &mut Self, to: f32) {
 ///        this.x = to;
-///        Ok(())fn has_bindgen_attr(attrs: &[syn::Attribute]) -> bool {
-///     for attr in attrs {
-///         if attr.path().is_ident("mlua_bindgen") {
-///             return true
-///         }
-///     }
-///     false
-/// }
 ///    }
 ///
 ///    #[method_mut]
-///    fn rename(_: _, this: &mut Self, to: &str) {
+///    fn rename(_: &Lua, this: &mut Self, to: String) {
 ///        this.name = to;
-///        Ok(())
 ///    }
 ///
 ///    #[func]
-///    fn make_new(_: _, ud: AnyUserData, name: &str) -> Self {
-///        Ok(Self {
+///    fn make_new(_: &Lua, ud: AnyUserData, name: String) -> Self {
+///        Self {
+///            x: 0.0,
 ///            name
-///        })
+///        }
 ///    }
 /// }
 /// ```
 /// ### Enums
 /// ```
+/// # use mlua::{Lua, Table};
+/// # use mlua_bindgen::mlua_bindgen;
 /// #[mlua_bindgen]
 /// enum Colors {
 ///     Red,
@@ -114,6 +115,7 @@ use mods::expand_mod;
 ///     Blue
 /// }
 ///
+/// # fn main() -> mlua::Result<()> {
 /// // Will automatically implement AsTable
 /// let lua = Lua::new();
 /// let lua_enum: Table = Colors::as_table(&lua)?;
@@ -123,14 +125,19 @@ use mods::expand_mod;
 /// //  Green = 1,
 /// //  Blue = 2,
 /// //}
+/// # Ok(())
+/// # }
 /// ```
 /// ### Modules
-/// ```rust
+/// ```
+/// # use mlua::Lua;
+/// # use mlua_bindgen::mlua_bindgen;
 /// #[mlua_bindgen]
 /// mod math {
+/// #   use mlua_bindgen::mlua_bindgen;
 ///     #[mlua_bindgen]
 ///     pub fn mul(_: &mlua::Lua, val1: f32, val2: f32) -> f32 {
-///         Ok(val1 * val2)
+///         val1 * val2
 ///     }
 /// }
 ///
@@ -138,23 +145,27 @@ use mods::expand_mod;
 // // And yes, the same can be done for the `math` module as well, but this is not shown here for simplicity.
 /// #[mlua_bindgen(include = [math_module])]
 /// mod utils {
+/// #   use mlua_bindgen::mlua_bindgen;
 ///     #[mlua_bindgen]
 ///     pub fn rust_hello(_: &mlua::Lua, who: String) {
 ///         println!("Hello to {who}");
-///         Ok(())
 ///     }
 /// }
 ///
+/// # fn main() -> mlua::Result<()> {
+/// # let lua = Lua::new();
 /// // This will automatically create a function that will
 /// // return ALL module items and included modules in a table.  
 ///
 /// lua.globals().set("utils", utils_module(&lua)?)?;
-/// lua.load('
+/// lua.load(r#"
 ///     utils.rust_hello("Lua!")
-/// ').exec()?;
+/// "#).exec()?;
 /// //
 /// // >> Hello to Lua!
 /// //
+/// # Ok(())
+/// # }
 /// ```
 #[proc_macro_attribute]
 pub fn mlua_bindgen(attr: TokenStream, input: TokenStream) -> TokenStream {
@@ -166,18 +177,16 @@ pub fn mlua_bindgen(attr: TokenStream, input: TokenStream) -> TokenStream {
     };
 
     match parse_item(input.clone()) {
-        ItemKind::Impl(item) => expand_impl(item),
-        ItemKind::Fn(item) => expand_fn(item),
-        ItemKind::Enum(item) => expand_enum(input, item),
-        ItemKind::Mod(item) => expand_mod(attrs, input, item),
-        ItemKind::Unsupported(item) => {
-            // Giving some useful tips
-            let msg = match item {
-                Item::Struct(_) => "If you want to implement a custom UserData type, you should put this macro on an impl block instead",
-                _ => "This macro can only be used on functions, enums, impl and mod blocks"
-            };
-            syn_error(item, msg).into_compile_error()
-        }
+        ItemKind::Impl(item) => expand_impl(attrs, item),
+        ItemKind::Fn(item) => expand_fn(attrs, item),
+        ItemKind::Enum(item) => expand_enum(attrs, input, item),
+        ItemKind::Mod(item) => expand_mod(attrs, item),
+        ItemKind::Struct(item) => expand_struct(attrs, item),
+        ItemKind::Unsupported(item) => syn_error(
+            item,
+            "This macro can only be used on functions, structs, enums, impl and mod blocks",
+        )
+        .into_compile_error(),
     }.into()
 }
 
@@ -185,13 +194,15 @@ pub fn mlua_bindgen(attr: TokenStream, input: TokenStream) -> TokenStream {
 /// 
 /// For example:
 /// ```
+/// # use mlua_bindgen::{mlua_bindgen, mlua_bindgen_ignore};
 /// #[mlua_bindgen(main)]
-/// pub env {
+/// pub mod env {
+/// #   use mlua_bindgen::{mlua_bindgen, mlua_bindgen_ignore};
 ///     
 ///     /// An item that we want to generate our bindings for
 ///     #[mlua_bindgen]
 ///     pub fn useful_func(_: &mlua::Lua, arg: u32) -> u32 {
-///         ...
+///         arg
 ///     }
 ///     
 ///     /// An item that we would like to ignore. (IDEs provide intellisense for require 
@@ -199,8 +210,8 @@ pub fn mlua_bindgen(attr: TokenStream, input: TokenStream) -> TokenStream {
 ///     /// developer experience worse)
 ///     #[mlua_bindgen]
 ///     #[mlua_bindgen_ignore]
-///     pub fn require(lua: &mlua::Lua, module: String) -> Table {
-///         ...
+///     pub fn require(lua: &mlua::Lua, module: String) -> mlua::Result<mlua::Table> {
+///         lua.create_table()
 ///     }
 /// 
 /// }
@@ -4,9 +4,9 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, ToTokens};
 use shared::{
     items::mods::{parse_mod, ModuleItem},
-    utils::{remove_lua_prefix, ItemAttributes, ToIdent},
+    utils::{syn_error, ItemAttributes, ToIdent},
 };
-use syn::ItemMod;
+use syn::{parse_quote, Item, ItemMod};
 
 use shared::mods::MODULE_SUFFIX;
 
@@ -18,17 +18,21 @@ use shared::mods::MODULE_SUFFIX;
 ///
 /// This is used to import other modules into the module space, and I think that's the best solution overall
 /// (In terms of parsing and convenience)
-pub fn expand_mod(attrs: ItemAttributes, input: TokenStream2, item: ItemMod) -> TokenStream2 {
+pub fn expand_mod(attrs: ItemAttributes, item: ItemMod) -> TokenStream2 {
+    let mut module = item.clone();
     let parsed_mod = match parse_mod(attrs, item, false) {
         Ok(parsed_mod) => parsed_mod,
         Err(err) => return err.into_compile_error(),
     };
     let mod_name = parsed_mod.ident.to_token_stream();
+    let (func_rules, type_rules) = (parsed_mod.func_rules(), parsed_mod.type_rules());
     let vis_param = parsed_mod.visibility.to_token_stream();
 
     // This is the container for all registration code. I called it exports because...
     // it "exports" its inner items into a separate function.
     let mut exports: Vec<TokenStream2> = Vec::new();
+    // Instances of generic items are registered from inside the module, where their type arguments are resolved
+    let mut instance_exports: Vec<TokenStream2> = Vec::new();
 
     for included in parsed_mod.includes {
         let path = included.path.to_token_stream();
@@ -50,43 +54,128 @@ pub fn expand_mod(attrs: ItemAttributes, input: TokenStream2, item: ItemMod) ->
                 // Unprefixed names exist in this case to separate lua functions/types from original
                 // rust functions/types.
                 //
-                // This function will remove any "lua" or "lua_" prefixes from the string,
-                // and automatically insert them in the table. THIS, however, doesn't rename the type/function's name
-                // - only their table key.
-                let unprefixed_name = remove_lua_prefix(name.to_string());
+                // Any "lua" or "lua_" prefixes are removed from the name (unless the item or the module is
+                // marked with `preserve`), and the result is used as the table key. THIS, however, doesn't
+                // rename the type/function's name - only their table key.
+                let lua_name = item.lua_name(type_rules);
 
                 quote! {
                     exports.set(
-                        #unprefixed_name,
+                        #lua_name,
                         #mod_name::#name::as_table(lua)?
                     )?;
                 }
             }
             ModuleItem::Fn(item) => {
-                let name = item.name.to_token_stream();
-                let unprefixed_name = remove_lua_prefix(name.to_string());
+                let fn_name = item.lua_name(func_rules);
+                // Kept functions are registered through their Lua adapter instead
+                let lua_name = item.lua_ident();
+                let create_fn = if item.is_async {
+                    quote! { create_async_function }
+                } else {
+                    quote! { create_function }
+                };
 
-                quote! {
-                    exports.set(
-                        #unprefixed_name,
-                        lua.create_function(#mod_name::#name)?
-                    )?;
+                if !item.is_generic() {
+                    quote! {
+                        exports.set(
+                            #fn_name,
+                            lua.#create_fn(#mod_name::#lua_name)?
+                        )?;
+                    }
+                } else if item.instantiations.is_empty() {
+                    return syn_error(
+                        &item.name,
+                        "Generic functions have to be instantiated to be used in modules (i.e. `#[mlua_bindgen(instantiate(T = mlua::Value))]`)",
+                    )
+                    .into_compile_error();
+                } else {
+                    // Every instance is registered as a separate function
+                    let instances = item.instantiations.iter().map(|instance| {
+                        let instance_name = item.instance_name(instance, func_rules);
+                        let types = item.instance_types(instance);
+                        quote! {
+                            exports.set(
+                                #instance_name,
+                                lua.#create_fn(#lua_name::<#(#types),*>)?
+                            )?;
+                        }
+                    });
+                    instance_exports.extend(instances);
+                    TokenStream2::new()
                 }
             }
-            ModuleItem::Impl(item) => {
+            // Parts are registered by the main impl block of their type
+            ModuleItem::Impl(item) if item.partial.is_some() => TokenStream2::new(),
+            // Types are exported as userdata proxies, which have both the class and the instance items registered
+            // on them (unless they're marked with `as_table`). Remote types are registered along with their module,
+            // and always exported as tables
+            ModuleItem::Impl(item) if !item.is_generic() => {
                 let name = item.name.to_token_stream();
-                let unprefixed_name = remove_lua_prefix(name.to_string());
+                let lua_name = item.lua_name(type_rules);
+                let class = match item.as_table {
+                    true => quote! { #mod_name::#name::as_table(lua)? },
+                    false => quote! { lua.create_proxy::<#mod_name::#name>()? },
+                };
+                let register = match item.remote {
+                    Some(_) => quote! { #mod_name::#name::register(lua)?; },
+                    None => TokenStream2::new(),
+                };
 
                 quote! {
+                    #register
                     exports.set(
-                        #unprefixed_name,
-                        #mod_name::#name::as_table(lua)?
+                        #lua_name,
+                        #class
                     )?;
                 }
             }
+            ModuleItem::Impl(item) => {
+                if item.instantiations.is_empty() {
+                    return syn_error(
+                        &item.name,
+                        "Generic impl blocks have to be instantiated to be used in modules (i.e. `#[mlua_bindgen(instantiate(T = mlua::Value))]`)",
+                    )
+                    .into_compile_error();
+                }
+
+                // Every instance is registered as a separate type table
+                let instances = item.instantiations.iter().map(|instance| {
+                    let instance_name = item.instance_name(instance, type_rules);
+                    let instance_ty = item.instance_ty(instance);
+                    let class = match item.as_table {
+                        true => quote! { <#instance_ty>::as_table(lua)? },
+                        false => quote! { lua.create_proxy::<#instance_ty>()? },
+                    };
+                    quote! {
+                        exports.set(
+                            #instance_name,
+                            #class
+                        )?;
+                    }
+                });
+                instance_exports.extend(instances);
+                TokenStream2::new()
+            }
+            // Only parsed for bindgen, since struct fields are registered by their impl
+            ModuleItem::Struct(_) => TokenStream2::new(),
         });
     }
 
+    if !instance_exports.is_empty() {
+        let register_instances: Item = parse_quote! {
+            #[doc(hidden)]
+            pub(super) fn __register_instances(lua: &::mlua::Lua, exports: &::mlua::Table) -> ::mlua::Result<()> {
+                #(#instance_exports)*
+                Ok(())
+            }
+        };
+        if let Some((_, items)) = &mut module.content {
+            items.push(register_instances);
+        }
+        exports.push(quote! { #mod_name::__register_instances(lua, &exports)?; });
+    }
+
     let post_init_func = match parsed_mod.post_init_func {
         Some(path) => {
             // We simply call our post_init function with lua and our exports table reference.
@@ -98,7 +187,7 @@ pub fn expand_mod(attrs: ItemAttributes, input: TokenStream2, item: ItemMod) ->
     let mod_name_module = format!("{mod_name}{MODULE_SUFFIX}").to_ident();
 
     quote! {
-        #input
+        #module
 
         #vis_param fn #mod_name_module(lua: &::mlua::Lua) -> ::mlua::Result<::mlua::Table> {
             let exports = lua.create_table()?;
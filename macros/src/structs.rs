@@ -0,0 +1,45 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use shared::{
+    items::structs::{parse_struct, remove_field_attrs},
+    utils::{ItemAttributes, ItemName, NameRules},
+};
+use syn::ItemStruct;
+
+use crate::impls::expand_field;
+
+/// Expand a struct with Lua fields. The struct itself is re-emitted without the field attributes, while its fields
+/// are registered through [`mlua_bindgen::__private::StructFields`], which the `#[mlua_bindgen]` impl block of the
+/// same type picks up in its [`mlua::UserData`] implementation.
+pub fn expand_struct(attrs: ItemAttributes, item: ItemStruct) -> TokenStream2 {
+    let mut original = item.clone();
+    remove_field_attrs(&mut original.fields);
+
+    let mut parsed_struct = match parse_struct(item) {
+        Ok(parsed) => parsed,
+        Err(err) => return err.into_compile_error(),
+    };
+    if let Err(err) = parsed_struct.apply_attrs(attrs) {
+        return err.into_compile_error();
+    }
+
+    let type_name = ItemName::default().lua_name(&parsed_struct.ident, NameRules::ITEM);
+    let field_rules = parsed_struct.field_rules();
+    let name = parsed_struct.ident;
+
+    let fields: Vec<TokenStream2> = parsed_struct
+        .fields
+        .into_iter()
+        .map(|field| expand_field(&type_name, field, field_rules, None))
+        .collect();
+
+    quote! {
+        #original
+
+        impl<F: ::mlua::UserDataFields<Self>> ::mlua_bindgen::__private::StructFields<F> for #name {
+            fn add_struct_fields(fields: &mut F) {
+                #(#fields)*
+            }
+        }
+    }
+}
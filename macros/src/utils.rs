@@ -1,26 +1,172 @@
 use proc_macro2::TokenStream as TokenStream2;
-use quote::ToTokens;
-use shared::funcs::FuncArg;
-
-/// Consume the argument vector, and return 3 vectors corresponding to:
-/// 1. Required argument names
-/// 2. User argument names
-/// 3. User argument types
-pub fn into_arg_tokens(
-    args: Vec<FuncArg>,
-) -> (Vec<TokenStream2>, Vec<TokenStream2>, Vec<TokenStream2>) {
-    let mut req_arg_names: Vec<TokenStream2> = Vec::new();
-    let mut usr_arg_names: Vec<TokenStream2> = Vec::new();
-    let mut usr_arg_types: Vec<TokenStream2> = Vec::new();
-
-    for arg in args {
-        if arg.required {
-            req_arg_names.push(arg.name.into_token_stream());
-        } else {
-            usr_arg_names.push(arg.name.into_token_stream());
-            usr_arg_types.push(arg.ty.into_token_stream());
+use quote::{quote, ToTokens};
+use shared::{
+    funcs::{result_ok_ty, ParsedFunc},
+    utils::syn_error,
+};
+use syn::{
+    punctuated::Punctuated, token::Comma, visit_mut::VisitMut, GenericParam, Ident, Type,
+    TypeInfer, WhereClause,
+};
+
+/// Build the where clause of a generic userdata impl. mlua requires userdata to be `'static`, so besides the user's
+/// own predicates, all type parameters are required to be `'static` as well.
+pub fn userdata_where_clause(
+    where_clause: &Option<WhereClause>,
+    params: &Punctuated<GenericParam, Comma>,
+) -> TokenStream2 {
+    let predicates = where_clause.iter().flat_map(|clause| &clause.predicates);
+    let static_params = params.iter().filter_map(|param| match param {
+        GenericParam::Type(ty_param) => Some(&ty_param.ident),
+        _ => None,
+    });
+    let mut static_params = static_params.peekable();
+    if where_clause.is_none() && static_params.peek().is_none() {
+        return TokenStream2::new();
+    }
+    quote! {
+        where #(#predicates,)* #(#static_params: 'static),*
+    }
+}
+
+/// Build the where clause of a binding. Arguments of generic bindings are converted from Lua inside of them,
+/// so besides the user's own predicates, they also need the conversion bounds (check `ArgTokens::conversion_bounds`).
+pub fn binding_where_clause(func: &ParsedFunc, conversion_bounds: &[TokenStream2]) -> TokenStream2 {
+    let where_clause = &func.generics.where_clause;
+    if !func.is_generic() {
+        return where_clause.to_token_stream();
+    }
+
+    let predicates = where_clause.iter().flat_map(|clause| &clause.predicates);
+    quote! {
+        where #(#predicates,)* #(#conversion_bounds),*
+    }
+}
+
+/// Generate static checks, which make sure the binding can be registered in Lua (by mlua rules).
+///
+/// Generic bindings are only checked with their instantiations, since the other types are picked by the user.
+pub fn static_checks(func: &ParsedFunc, lua_ident: &Ident) -> TokenStream2 {
+    let create_fn = match func.is_async {
+        true => quote! { create_async_function },
+        false => quote! { create_function },
+    };
+    let check = |binding: TokenStream2| {
+        quote! {
+            const _:fn(&::mlua::Lua) = |l| {
+                _ = l.#create_fn(#binding);
+            };
         }
+    };
+
+    if !func.is_generic() {
+        return check(lua_ident.to_token_stream());
+    }
+    func.instantiations
+        .iter()
+        .map(|instance| {
+            let types = func.instance_types(instance);
+            check(quote! { #lua_ident::<#(#types),*> })
+        })
+        .collect()
+}
+
+/// Async bindings use mlua's async API, which is only available with the `async` feature. This will
+/// return an error if the function is async while the feature is disabled.
+pub fn check_async(func: &ParsedFunc) -> syn::Result<()> {
+    if func.is_async && !cfg!(feature = "async") {
+        return Err(syn_error(
+            &func.name,
+            "Async functions require the `async` feature of mlua_bindgen (and mlua)",
+        ));
+    }
+    Ok(())
+}
+
+/// Convert a result expression of a binding (which can have any supported error type) into an `mlua::Result`.
+///
+/// The conversion itself is picked at compile time by `mlua_bindgen::__private::ErrorWrap`: errors that
+/// implement `Into<mlua::Error>` are converted directly, while the rest are wrapped as external errors.
+pub fn convert_result(result: TokenStream2) -> TokenStream2 {
+    quote! {
+        ::std::result::Result::map_err(#result, |err| {
+            use ::mlua_bindgen::__private::{ViaAnyhow as _, ViaInto as _, ViaStdError as _};
+            (&&&::mlua_bindgen::__private::ErrorWrap::<_, ::mlua::Error>::new(err))
+                .to_lua_error(|err| ::mlua::Error::ExternalError(err))
+        })
     }
+}
+
+/// Wrap the body of a rewritten binding, so it evaluates to an `mlua::Result` (or its future, for async bindings).
+///
+/// A plain return type (`-> T`) means the binding always succeeds, so its value is simply wrapped into `Ok`. When
+/// the binding declares a result instead (`-> Result<T, E>`), the body is evaluated into it and its error is
+/// converted.
+pub fn rewritten_body(func: &ParsedFunc) -> TokenStream2 {
+    let (block, return_ty) = (&func.block, &func.return_ty);
+
+    // The block is evaluated separately (as a closure, or an async block, just like the body of an async fn), so its
+    // `return` and `?` expressions keep working. `impl Trait` can only be written in function signatures, so the
+    // wrapper leaves those types to inference
+    let mut value_ty = return_ty.clone();
+    InferImplTraits.visit_type_mut(&mut value_ty);
+    let value = match func.is_async {
+        true => quote! {
+            ::mlua_bindgen::__private::typed_future::<#value_ty, _>(async move #block).await
+        },
+        false => quote! { (move || -> #value_ty #block)() },
+    };
+    if result_ok_ty(return_ty).is_none() {
+        return quote! { { ::std::result::Result::Ok::<_, ::mlua::Error>(#value) } };
+    }
+    let converted = convert_result(value);
+    quote! { { #converted } }
+}
+
+/// Replaces `impl Trait` types with `_`
+struct InferImplTraits;
+
+impl VisitMut for InferImplTraits {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::ImplTrait(impl_trait) = ty {
+            *ty = Type::Infer(TypeInfer {
+                underscore_token: syn::Token![_](impl_trait.impl_token.span),
+            });
+            return;
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+/// The type a binding returns to Lua. Results are peeled, since their errors are raised instead
+pub fn lua_return_ty(return_ty: &Type) -> &Type {
+    result_ok_ty(return_ty).unwrap_or(return_ty)
+}
+
+/// Call a kept function from its Lua adapter, and convert its return value into an `mlua::Result`
+pub fn kept_call(
+    func: &ParsedFunc,
+    callee: TokenStream2,
+    call_args: &[TokenStream2],
+) -> TokenStream2 {
+    let await_param = match func.is_async {
+        true => quote! { .await },
+        false => TokenStream2::new(),
+    };
+    let call = quote! { #callee(#(#call_args), *)#await_param };
+
+    match result_ok_ty(&func.return_ty) {
+        Some(_) => convert_result(call),
+        None => quote! { ::std::result::Result::Ok(#call) },
+    }
+}
 
-    (req_arg_names, usr_arg_names, usr_arg_types)
+/// The id of a userdata part (`partial = name`), which its main impl block looks it up by. It's a 64-bit FNV-1a
+/// hash of the part name, since const generics can't take strings
+pub fn part_id(part: &Ident) -> u64 {
+    part.to_string()
+        .bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
 }
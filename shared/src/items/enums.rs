@@ -1,12 +1,16 @@
 use syn::{Expr, Ident, ItemEnum, Lit};
 
-use crate::utils::{contains_attr, syn_error, MLUA_IGNORE_BINDGEN_ATTR};
+use crate::utils::{
+    contains_attr, syn_error, ItemAttributes, ItemName, NameRules, MLUA_IGNORE_BINDGEN_ATTR,
+};
 
 pub type LuaVariantType = usize;
 
 /// Contains general enum information, that is important both for macros and bindgen parsers
 pub struct ParsedEnum {
     pub ident: Ident,
+    /// Naming attributes of the enum (see [`ParsedEnum::lua_name`])
+    pub naming: ItemName,
     pub bindgen_ignore: bool,
     pub variants: Vec<(Ident, LuaVariantType)>,
 }
@@ -16,10 +20,29 @@ impl ParsedEnum {
     pub fn from_ident(ident: Ident) -> Self {
         Self {
             ident,
+            naming: ItemName::default(),
             bindgen_ignore: false,
             variants: Vec::new(),
         }
     }
+
+    /// Apply enum-level [`mlua_bindgen`] attributes (i.e. `#[mlua_bindgen(name = "Color")]`) to this enum
+    pub fn apply_attrs(&mut self, attrs: ItemAttributes) -> syn::Result<()> {
+        for attr in attrs.0 {
+            if !self.naming.apply_attr(&attr) {
+                return Err(syn_error(
+                    &self.ident,
+                    "Only the `name` and `preserve` attributes can be used on enums",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the Lua name of the enum under the naming rules of its module
+    pub fn lua_name(&self, rules: NameRules) -> String {
+        self.naming.lua_name(&self.ident, rules)
+    }
 }
 
 /// Parse an [`ItemEnum`] into [`ParsedEnum`].
@@ -63,5 +86,10 @@ pub fn parse_enum(item: ItemEnum) -> syn::Result<ParsedEnum> {
         value += 1;
     }
 
-    Ok(ParsedEnum { ident, bindgen_ignore, variants })
+    Ok(ParsedEnum {
+        ident,
+        naming: ItemName::default(),
+        bindgen_ignore,
+        variants,
+    })
 }
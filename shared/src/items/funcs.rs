@@ -1,18 +1,30 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
 use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_quote,
     punctuated::Punctuated,
     token::{Brace, Comma, Paren},
-    Block, FnArg, Ident, ImplItemFn, ItemFn, Pat, ReturnType, Type, TypeTuple, Visibility,
+    visit_mut::{self, VisitMut},
+    Attribute, Block, Expr, ExprRange, FnArg, GenericArgument, GenericParam, Generics, Ident,
+    ImplItemFn, ItemFn, LitStr, MetaNameValue, Pat, PatType, Path, PathArguments, Receiver,
+    ReturnType, Token, Type, TypeTuple, Visibility,
 };
 
-use crate::utils::{contains_attr, syn_error, MLUA_IGNORE_BINDGEN_ATTR};
+use crate::utils::{
+    contains_attr, syn_error, ItemAttribute, ItemAttributes, ItemName, LastPathIdent, NameRules,
+    ToIdent, MLUA_IGNORE_BINDGEN_ATTR,
+};
 
 pub struct CommonFuncInfo {
     pub ident: Ident,
+    pub attrs: Vec<Attribute>,
     pub bindgen_ignore: bool,
+    pub is_async: bool,
     pub visibility: Visibility,
     pub block: Block,
+    pub generics: Generics,
     pub ret_ty: ReturnType,
     pub args: Punctuated<FnArg, Comma>,
 }
@@ -28,8 +40,11 @@ impl CommonFunc for ImplItemFn {
         CommonFuncInfo {
             ident: self.sig.ident,
             bindgen_ignore: contains_attr(&self.attrs, MLUA_IGNORE_BINDGEN_ATTR),
+            attrs: self.attrs,
+            is_async: self.sig.asyncness.is_some(),
             visibility: self.vis,
             block: self.block,
+            generics: self.sig.generics,
             ret_ty: self.sig.output,
             args: self.sig.inputs,
         }
@@ -41,15 +56,19 @@ impl CommonFunc for ItemFn {
         CommonFuncInfo {
             ident: self.sig.ident,
             bindgen_ignore: contains_attr(&self.attrs, MLUA_IGNORE_BINDGEN_ATTR),
+            attrs: self.attrs,
+            is_async: self.sig.asyncness.is_some(),
             visibility: self.vis,
             block: *self.block,
+            generics: self.sig.generics,
             ret_ty: self.sig.output,
             args: self.sig.inputs,
         }
     }
 }
 
-/// A simple enum representing 3 possible function types. Basically the same explanation as with the [`FieldKind`]
+/// A simple enum representing all possible function types. Basically the same explanation as with the [`FieldKind`]
+#[derive(Clone, Copy)]
 pub enum FuncKind {
     /// Lua method
     Method,
@@ -57,16 +76,381 @@ pub enum FuncKind {
     MethodMut,
     /// Lua class method
     Func,
+    /// A function of the userdata itself (`add_function`). Unlike methods, it receives the userdata as a regular
+    /// argument (i.e. `AnyUserData`), if it's called with a colon
+    Function,
+    /// The same as [`FuncKind::Function`], but the function can mutate its state (`add_function_mut`)
+    FunctionMut,
     /// Lua table's meta-method
     Meta,
+    /// Lua meta-method that receives the userdata itself (a `#[meta]` function with a `&self` or `self` receiver)
+    MetaMethod,
+    /// The same as [`FuncKind::MetaMethod`], but with a `&mut self` receiver
+    MetaMethodMut,
+}
+
+impl FuncKind {
+    /// Whether functions of this kind receive the userdata (`&Self` or `&mut Self`) after the Lua state
+    pub fn takes_this(&self) -> bool {
+        matches!(
+            self,
+            Self::Method | Self::MethodMut | Self::MetaMethod | Self::MetaMethodMut
+        )
+    }
+}
+
+/// A real `self` receiver of a method (instead of a `this: &Self` argument). In bindings it's renamed to `this`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SelfReceiver {
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+    /// `self` or `mut self`. The value is cloned out of the userdata, so the type has to implement `Clone`
+    Value { mutable: bool },
+}
+
+impl SelfReceiver {
+    /// Parse a receiver. Only `Self` itself and its references are supported (i.e. not `self: Box<Self>`)
+    fn parse(receiver: &Receiver) -> syn::Result<Self> {
+        let is_self =
+            |ty: &Type| matches!(ty, Type::Path(ty_path) if ty_path.path.is_ident("Self"));
+        match &*receiver.ty {
+            Type::Reference(ty_ref) if is_self(&ty_ref.elem) => match ty_ref.mutability {
+                Some(_) => Ok(Self::RefMut),
+                None => Ok(Self::Ref),
+            },
+            ty if is_self(ty) => Ok(Self::Value {
+                mutable: receiver.mutability.is_some(),
+            }),
+            _ => Err(syn_error(
+                receiver,
+                "Only `self`, `&self` and `&mut self` receivers are supported",
+            )),
+        }
+    }
+
+    /// The type of the userdata mlua passes to the binding
+    fn this_ty(&self) -> Type {
+        match self {
+            Self::RefMut => parse_quote! { &mut Self },
+            Self::Ref | Self::Value { .. } => parse_quote! { &Self },
+        }
+    }
+}
+
+/// Replace all `self` keywords with `this`, so a method body can be pasted into a binding closure. Paths
+/// that start with `self::` refer to the module instead, and are left as they are. Nested items (`impl`,
+/// `trait` and `fn` items) have their own `self`, so they're left as they are too.
+fn replace_self(tokens: TokenStream) -> TokenStream {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut replaced = Vec::new();
+    while let Some(token) = tokens.next() {
+        replaced.push(match token {
+            TokenTree::Ident(ident) if is_item_start(&ident, tokens.peek()) => {
+                replaced.push(TokenTree::Ident(ident));
+                replaced.extend(take_item(&mut tokens));
+                continue;
+            }
+            TokenTree::Ident(ident) if ident == "self" => {
+                let is_path =
+                    matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':');
+                match is_path {
+                    true => TokenTree::Ident(ident),
+                    false => TokenTree::Ident(Ident::new("this", ident.span())),
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut new_group = Group::new(group.delimiter(), replace_self(group.stream()));
+                new_group.set_span(group.span());
+                TokenTree::Group(new_group)
+            }
+            token => token,
+        });
+    }
+    replaced.into_iter().collect()
 }
 
+/// Find the first `Self` keyword in a function body. Nested items have their own `Self`, so they're skipped
+pub(crate) fn find_self_ty(tokens: TokenStream) -> Option<Ident> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if is_item_start(&ident, tokens.peek()) => {
+                take_item(&mut tokens);
+            }
+            TokenTree::Ident(ident) if ident == "Self" => return Some(ident),
+            TokenTree::Group(group) => {
+                if let Some(ident) = find_self_ty(group.stream()) {
+                    return Some(ident);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Take the rest of a nested item (after its keyword), up to the end of its body (including it)
+fn take_item(tokens: &mut impl Iterator<Item = TokenTree>) -> Vec<TokenTree> {
+    let mut item = Vec::new();
+    for token in tokens {
+        let is_body =
+            matches!(&token, TokenTree::Group(group) if group.delimiter() == Delimiter::Brace);
+        item.push(token);
+        if is_body {
+            break;
+        }
+    }
+    item
+}
+
+/// Check whether the ident starts a nested item. Functions are only items when they're named, since
+/// function pointer types (`fn(u32) -> u32`) can be used in bodies as well
+fn is_item_start(ident: &Ident, next: Option<&TokenTree>) -> bool {
+    match ident.to_string().as_str() {
+        "impl" | "trait" => true,
+        "fn" => matches!(next, Some(TokenTree::Ident(_))),
+        _ => false,
+    }
+}
+
+#[derive(Clone)]
 pub struct FuncArg {
     pub name: Pat,
     pub ty: Type,
     /// A required argument is the one that's important for mlua API. Usually it's a `&Lua` reference, or
     /// `&Self` / `&mut Self` in methods.
     pub required: bool,
+    /// Whether this required argument was inserted by the macro, since the user's function doesn't declare it
+    pub inserted: bool,
+    /// The default value of an optional argument (i.e. `#[default(1.0)] scale: f32`). Lua can omit such
+    /// arguments, in which case this expression is used instead.
+    pub default: Option<Expr>,
+    /// Validation checks of this argument (i.e. `#[range(0..=100)]`), in their declaration order
+    pub checks: Vec<ArgCheck>,
+    /// The Lua type of this argument for bindgen (i.e. `#[lua_type("(number) -> boolean")]`), which is used
+    /// as it is instead of the Rust type
+    pub lua_type: Option<String>,
+}
+
+/// A declarative argument check. Failed checks raise a bad argument error in Lua.
+///
+/// Checks of optional (`Option<T>`) arguments only apply when the value is present.
+#[derive(Clone)]
+pub enum ArgCheck {
+    /// `#[range(0..=100)]`: the value has to be contained in the range
+    Range(ExprRange),
+    /// `#[len(min = 1, max = 64)]`: the value's `len()` has to be within the bounds (both are optional)
+    Len {
+        min: Option<Box<Expr>>,
+        max: Option<Box<Expr>>,
+    },
+    /// `#[non_empty]`: the value can't be empty (checked with `is_empty()`)
+    NonEmpty,
+    /// `#[validate(path::to::fn)]`: a custom validator. It takes the value by reference, and returns
+    /// a `Result<(), E>`, where `E` is the message of the error
+    Validate(Path),
+}
+
+impl ArgCheck {
+    /// Try to parse an argument attribute as a check. Returns `None` for unrelated attributes
+    fn from_attr(attr: &Attribute) -> syn::Result<Option<Self>> {
+        let path = attr.path();
+        let check = if path.is_ident("range") {
+            match attr.parse_args::<Expr>()? {
+                Expr::Range(range) => Self::Range(range),
+                expr => return Err(syn_error(expr, "Expected a range (i.e. `0..=100`)")),
+            }
+        } else if path.is_ident("len") {
+            let (mut min, mut max) = (None, None);
+            let bounds =
+                attr.parse_args_with(Punctuated::<MetaNameValue, Comma>::parse_terminated)?;
+            for bound in bounds {
+                if bound.path.is_ident("min") {
+                    min = Some(Box::new(bound.value));
+                } else if bound.path.is_ident("max") {
+                    max = Some(Box::new(bound.value));
+                } else {
+                    return Err(syn_error(bound.path, "Expected either `min` or `max`"));
+                }
+            }
+            if min.is_none() && max.is_none() {
+                return Err(syn_error(attr, "Expected at least one bound (i.e. `max = 64`)"));
+            }
+            Self::Len { min, max }
+        } else if path.is_ident("non_empty") {
+            attr.meta.require_path_only()?;
+            Self::NonEmpty
+        } else if path.is_ident("validate") {
+            Self::Validate(attr.parse_args::<Path>()?)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(check))
+    }
+
+    /// Describe the requirement of this check (i.e. "must be in range 0..=100"). It's used both in error
+    /// messages and in the generated bindings
+    pub fn describe(&self) -> String {
+        // Token streams are stringified with spaces between all tokens, which looks odd in messages
+        let tokens = |tokens: &dyn ToTokens| tokens.to_token_stream().to_string().replace(' ', "");
+        match self {
+            Self::Range(range) => format!("must be in range {}", tokens(range)),
+            Self::Len {
+                min: Some(min),
+                max: Some(max),
+            } => format!("must have a length between {} and {}", tokens(min), tokens(max)),
+            Self::Len { min: Some(min), .. } => format!("must have a length of at least {}", tokens(min)),
+            Self::Len { max, .. } => format!("must have a length of at most {}", tokens(max)),
+            Self::NonEmpty => "must not be empty".to_owned(),
+            Self::Validate(path) => format!("must pass `{}`", tokens(path)),
+        }
+    }
+}
+
+impl FuncArg {
+    /// Construct an inserted required argument with an ignored name
+    fn inserted(ty: Type) -> Self {
+        Self {
+            name: parse_quote! { _ },
+            ty,
+            required: true,
+            inserted: true,
+            default: None,
+            checks: Vec::new(),
+            lua_type: None,
+        }
+    }
+
+    /// Get the name of the argument, if it's a simple identifier pattern (i.e. `x` or `mut x`)
+    pub fn ident(&self) -> Option<&Ident> {
+        match &self.name {
+            Pat::Ident(pat_ident) => Some(&pat_ident.ident),
+            _ => None,
+        }
+    }
+
+    /// Parse a typed function argument, along with its argument attributes
+    fn from_typed(arg: PatType) -> syn::Result<Self> {
+        let mut default = None;
+        let mut checks = Vec::new();
+        let mut lua_type = None;
+        for attr in arg.attrs.iter() {
+            if attr.path().is_ident("default") {
+                default = Some(attr.parse_args::<Expr>()?);
+            } else if attr.path().is_ident("lua_type") {
+                lua_type = Some(attr.parse_args::<LitStr>()?.value());
+            } else if let Some(check) = ArgCheck::from_attr(attr)? {
+                checks.push(check);
+            }
+        }
+
+        let arg = Self {
+            name: *arg.pat,
+            ty: *arg.ty,
+            required: false,
+            inserted: false,
+            default,
+            checks,
+            lua_type,
+        };
+        // Checks are generated for the argument's binding, so it has to have one
+        if !arg.checks.is_empty() && arg.ident().is_none() {
+            return Err(syn_error(
+                &arg.name,
+                "Validated arguments have to be named with a simple identifier",
+            ));
+        }
+        Ok(arg)
+    }
+}
+
+/// Argument attributes that are handled by the macro. They aren't valid Rust attributes, so they have to be removed
+/// from any function that's emitted back (see [`remove_arg_attrs`])
+pub const ARG_ATTRS: [&str; 6] = [
+    "default",
+    "range",
+    "len",
+    "non_empty",
+    "validate",
+    "lua_type",
+];
+
+/// Function attributes that declare Lua types for bindgen: `#[lua_return("...")]` on functions, and
+/// `#[lua_type("...")]` on getters. Like [`ARG_ATTRS`], they have to be removed from emitted functions
+/// (see [`remove_func_attrs`])
+pub const FUNC_ATTRS: [&str; 2] = ["lua_return", "lua_type"];
+
+/// Remove [`ARG_ATTRS`] from the function arguments
+pub fn remove_arg_attrs(inputs: &mut Punctuated<FnArg, Comma>) {
+    for input in inputs.iter_mut() {
+        if let FnArg::Typed(arg) = input {
+            arg.attrs
+                .retain(|attr| !ARG_ATTRS.iter().any(|name| attr.path().is_ident(name)));
+        }
+    }
+}
+
+/// Remove [`FUNC_ATTRS`] from the function attributes
+pub fn remove_func_attrs(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|attr| !FUNC_ATTRS.iter().any(|name| attr.path().is_ident(name)));
+}
+
+/// If the type is a result (i.e. `mlua::Result<T>` or `Result<T, E>`), return its `T` type.
+pub fn result_ok_ty(ty: &Type) -> Option<&Type> {
+    let ty_path = match ty {
+        Type::Path(ty_path) => ty_path,
+        _ => return None,
+    };
+
+    let segment = ty_path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(ok_ty) => Some(ok_ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Check whether the argument type is a Lua state: `&Lua`, `Lua` (for async functions), or an inferred type `_`,
+/// which is how most closure-style bindings declare it.
+pub fn is_lua_ty(ty: &Type) -> bool {
+    match ty {
+        Type::Infer(_) => true,
+        Type::Reference(ty_ref) => {
+            matches!(&*ty_ref.elem, Type::Path(ty_path) if ty_path.path.last_ident() == "Lua")
+        }
+        Type::Path(ty_path) => ty_path.path.last_ident() == "Lua",
+        _ => false,
+    }
+}
+
+/// Check whether the type is an `Option<T>`
+pub fn is_option_ty(ty: &Type) -> bool {
+    matches!(ty, Type::Path(ty_path) if ty_path.path.last_ident() == "Option")
+}
+
+/// Check whether the type takes a variable amount of Lua values (`mlua::Variadic<T>` or `MultiValue`).
+pub fn is_variadic_ty(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty_path) => {
+            let ident = ty_path.path.last_ident();
+            ident == "Variadic" || ident == "MultiValue"
+        }
+        _ => false,
+    }
+}
+
+/// Check whether the argument type can be a method's `this` argument. Lua can't pass references to
+/// user arguments, so any reference (or an inferred `_` type) is considered to be `&Self`/`&mut Self`.
+pub fn is_this_ty(ty: &Type) -> bool {
+    matches!(ty, Type::Infer(_) | Type::Reference(_))
 }
 
 impl ToTokens for FuncArg {
@@ -89,16 +473,163 @@ impl ToTokens for FuncArg {
     }
 }
 
+/// An explicit instantiation of a generic function (i.e. `instantiate(T = i64)`). Each instance is registered
+/// in Lua as a separate function.
+#[derive(Clone)]
+pub struct Instantiation {
+    /// The Lua name of the instance. Unnamed instances are registered under the name of the function itself
+    pub name: Option<Ident>,
+    /// Generic type parameters, along with the types they're substituted with
+    pub args: Vec<(Ident, Type)>,
+}
+
+impl Instantiation {
+    /// Parse the contents of `instantiate(...)`. It's either a single unnamed instance (`T = i64, U = f64`),
+    /// or a list of named ones (`int_id(T = i64), num_id(T = f64)`).
+    pub fn parse_list(input: ParseStream) -> syn::Result<Vec<Self>> {
+        if input.peek(Ident) && input.peek2(Token![=]) {
+            let args = Self::parse_args(input)?;
+            return Ok(vec![Self { name: None, args }]);
+        }
+
+        let instances = Punctuated::<Self, Comma>::parse_terminated(input)?;
+        Ok(instances.into_iter().collect())
+    }
+
+    /// Parse a list of type parameter substitutions: `T = i64, U = f64`
+    fn parse_args(input: ParseStream) -> syn::Result<Vec<(Ident, Type)>> {
+        let args = Punctuated::<(Ident, Type), Comma>::parse_terminated_with(input, |input| {
+            let param = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            Ok((param, input.parse::<Type>()?))
+        })?;
+        Ok(args.into_iter().collect())
+    }
+
+    /// Check that every instance substitutes all type parameters of `item` (a function or an impl block) exactly
+    /// once, and that instances are registered under distinct Lua names
+    pub fn check_list(
+        instances: &[Self],
+        generics: &Generics,
+        item: &Ident,
+        instance_name: impl Fn(&Self) -> String,
+    ) -> syn::Result<()> {
+        let mut names = Vec::new();
+        for instance in instances.iter() {
+            for (param, _) in instance.args.iter() {
+                if !generics
+                    .type_params()
+                    .any(|ty_param| &ty_param.ident == param)
+                {
+                    return Err(syn_error(
+                        param,
+                        format!("`{param}` isn't a type parameter of `{item}`"),
+                    ));
+                }
+                if instance
+                    .args
+                    .iter()
+                    .filter(|(other, _)| other == param)
+                    .count()
+                    > 1
+                {
+                    return Err(syn_error(
+                        param,
+                        format!("`{param}` is instantiated more than once"),
+                    ));
+                }
+            }
+            let span = instance.name.as_ref().unwrap_or(item);
+            if let Some(ty_param) = generics.type_params().find(|ty_param| {
+                !instance
+                    .args
+                    .iter()
+                    .any(|(param, _)| param == &ty_param.ident)
+            }) {
+                return Err(syn_error(
+                    span,
+                    format!("Missing a type for the `{}` parameter", ty_param.ident),
+                ));
+            }
+
+            let name = instance_name(instance);
+            if names.contains(&name) {
+                return Err(syn_error(
+                    span,
+                    format!("Instances have to be registered under distinct names (`{name}` is repeated)"),
+                ));
+            }
+            names.push(name);
+        }
+        Ok(())
+    }
+}
+
+impl Parse for Instantiation {
+    /// Parse a named instance: `int_id(T = i64)`
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<Ident>()?;
+        let content;
+        parenthesized!(content in input);
+        let args = Self::parse_args(&content)?;
+        Ok(Self {
+            name: Some(name),
+            args,
+        })
+    }
+}
+
+/// Replaces generic type parameters in types with their instantiated types
+pub(crate) struct SubstituteGenerics<'a>(pub(crate) &'a [(Ident, Type)]);
+
+impl VisitMut for SubstituteGenerics<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(ty_path) = ty {
+            if ty_path.qself.is_none() {
+                let found = self
+                    .0
+                    .iter()
+                    .find(|(param, _)| ty_path.path.is_ident(param));
+                if let Some((_, substituted)) = found {
+                    *ty = substituted.clone();
+                    return;
+                }
+            }
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+}
+
 /// The absolute extracted function. The huge difference between this and [`FuncInfo`] is that the latter contains
 /// only the most basic information about the function. This contains additional information like user argument types,
 /// user argument names, and so on.
+#[derive(Clone)]
 pub struct ParsedFunc {
     pub name: Ident,
+    /// Naming attributes of the function (see [`ParsedFunc::lua_name`])
+    pub naming: ItemName,
     pub bindgen_ignore: bool,
+    /// Async functions are registered through mlua's async API (i.e. `create_async_function`),
+    /// which is only available with the `async` feature.
+    pub is_async: bool,
+    /// Whether the original function is kept as it is. In that case, Lua is given a separate
+    /// adapter function instead (see [`ParsedFunc::lua_ident`])
+    pub keep: bool,
+    /// Whether the function takes all its user arguments from a single Lua table, by their names
+    pub table_args: bool,
     pub visibility: Visibility,
     pub block: Block,
+    pub generics: Generics,
+    /// Explicit instantiations of a generic function. Generic functions can't be registered in Lua
+    /// without picking their types first
+    pub instantiations: Vec<Instantiation>,
     pub args: Vec<FuncArg>,
+    /// The `self` receiver of a method, if it's declared with one. Its body then refers to `this` instead
+    pub receiver: Option<SelfReceiver>,
     pub return_ty: Type,
+    /// The Lua return type for bindgen (i.e. `#[lua_return("{string}")]`), which is used as it is
+    /// instead of the Rust type
+    pub lua_return: Option<String>,
 }
 
 impl ParsedFunc {
@@ -108,19 +639,169 @@ impl ParsedFunc {
     pub fn from_ident(name: Ident) -> Self {
         Self {
             name,
+            naming: ItemName::default(),
             bindgen_ignore: false,
+            is_async: false,
+            keep: false,
+            table_args: false,
             visibility: Visibility::Inherited,
             block: Block {
                 brace_token: Brace::default(),
                 stmts: Vec::new(),
             },
+            generics: Generics::default(),
+            instantiations: Vec::new(),
             args: Vec::new(),
+            receiver: None,
             return_ty: Type::Verbatim(TokenStream::new()),
+            lua_return: None,
         }
     }
 }
 
 impl ParsedFunc {
+    /// Apply function-level [`mlua_bindgen`] attributes (i.e. `#[mlua_bindgen(keep)]`) to this function
+    pub fn apply_attrs(&mut self, attrs: ItemAttributes) -> syn::Result<()> {
+        for attr in attrs.0 {
+            match attr {
+                ItemAttribute::Keep => self.keep = true,
+                ItemAttribute::TableArgs => self.table_args = true,
+                ItemAttribute::Instantiate(instances) => self.instantiations.extend(instances),
+                attr if self.naming.apply_attr(&attr) => {}
+                _ => {
+                    return Err(syn_error(
+                        &self.name,
+                        "Only the `keep`, `table_args`, `instantiate`, `name` and `preserve` attributes can be used on functions",
+                    ))
+                }
+            }
+        }
+
+        if self.table_args {
+            self.check_table_args()?;
+        }
+        self.check_instantiations()?;
+        Ok(())
+    }
+
+    /// Every instance has to substitute all type parameters of the function, and has to be registered
+    /// under a distinct name.
+    fn check_instantiations(&self) -> syn::Result<()> {
+        if self.instantiations.is_empty() {
+            return Ok(());
+        }
+        if !self.is_generic() {
+            return Err(syn_error(
+                &self.name,
+                "Only generic functions can be instantiated",
+            ));
+        }
+        if let Some(param) = self.generics.const_params().next() {
+            return Err(syn_error(
+                param,
+                "Functions with const parameters can't be instantiated",
+            ));
+        }
+
+        Instantiation::check_list(
+            &self.instantiations,
+            &self.generics,
+            &self.name,
+            |instance| self.instance_name(instance, NameRules::ITEM),
+        )?;
+        Ok(())
+    }
+
+    /// Whether the function has any type parameters (lifetimes don't count)
+    pub fn is_generic(&self) -> bool {
+        self.generics
+            .params
+            .iter()
+            .any(|param| !matches!(param, GenericParam::Lifetime(_)))
+    }
+
+    /// Get the Lua name of the function under the naming rules of its parent
+    pub fn lua_name(&self, rules: NameRules) -> String {
+        self.naming.lua_name(&self.name, rules)
+    }
+
+    /// Get the Lua name of an instance. Named instances are named by their own name instead of the function's
+    pub fn instance_name(&self, instance: &Instantiation, rules: NameRules) -> String {
+        match &instance.name {
+            Some(name) => ItemName {
+                rename: None,
+                preserve: self.naming.preserve,
+            }
+            .lua_name(name, rules),
+            None => self.lua_name(rules),
+        }
+    }
+
+    /// Get the instantiated types, in the order of the function's type parameters (i.e. for a turbofish)
+    pub fn instance_types<'a>(&self, instance: &'a Instantiation) -> Vec<&'a Type> {
+        self.generics
+            .type_params()
+            .filter_map(|ty_param| {
+                instance
+                    .args
+                    .iter()
+                    .find(|(param, _)| param == &ty_param.ident)
+                    .map(|(_, ty)| ty)
+            })
+            .collect()
+    }
+
+    /// Create a concrete copy of this function, with all type parameters substituted by the instance's types.
+    /// Unnamed instances keep the function's name.
+    pub fn instantiate(&self, instance: &Instantiation) -> Self {
+        let mut func = self.clone();
+        func.substitute(&instance.args);
+
+        if let Some(name) = &instance.name {
+            func.name = name.clone();
+            func.naming.rename = None;
+        }
+        func.generics = Generics::default();
+        func.instantiations = Vec::new();
+        func
+    }
+
+    /// Substitute type parameters in the argument and return types (i.e. of the impl block the function is in)
+    pub fn substitute(&mut self, args: &[(Ident, Type)]) {
+        let mut substitute = SubstituteGenerics(args);
+        for arg in self.args.iter_mut() {
+            substitute.visit_type_mut(&mut arg.ty);
+        }
+        substitute.visit_type_mut(&mut self.return_ty);
+    }
+
+    /// Table arguments are taken by their names, so they have to be simple identifiers. Variadic
+    /// arguments also don't make sense in a table.
+    fn check_table_args(&self) -> syn::Result<()> {
+        for arg in self.args.iter().filter(|arg| !arg.required) {
+            if arg.ident().is_none() {
+                return Err(syn_error(
+                    &arg.name,
+                    "Table arguments have to be named with a simple identifier",
+                ));
+            }
+            if is_variadic_ty(&arg.ty) {
+                return Err(syn_error(&arg.ty, "Table arguments can't be variadic"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the identity of the function that should be registered in Lua. For kept functions it's
+    /// their generated adapter (`{name}_lua`), while for the rest - the function itself.
+    pub fn lua_ident(&self) -> Ident {
+        if self.keep {
+            format!("{}_lua", self.name).to_ident()
+        } else {
+            self.name.clone()
+        }
+    }
+
     /// Get the amount of user arguments
     pub fn user_arg_count(&self) -> usize {
         self.args.iter().filter(|arg| !arg.required).count()
@@ -136,8 +817,22 @@ pub fn parse_func(item: impl CommonFunc, kind: &FuncKind) -> syn::Result<ParsedF
     let info = item.get_info();
     let name = info.ident;
     let bindgen_ignore = info.bindgen_ignore;
+    let is_async = info.is_async;
     let block = info.block;
     let visibility = info.visibility;
+    let generics = info.generics;
+
+    let mut lua_return = None;
+    for attr in info.attrs.iter() {
+        if attr.path().is_ident("lua_return") {
+            lua_return = Some(attr.parse_args::<LitStr>()?.value());
+        } else if attr.path().is_ident("lua_type") {
+            return Err(syn_error(
+                attr,
+                "`lua_type` can only be used on arguments and getters. Use `lua_return` for return types instead",
+            ));
+        }
+    }
 
     // Signature output returns both the return type and the arrow ("-> u32", as example), so we filter it with
     // the match statement here, and convert to tokens
@@ -154,55 +849,120 @@ pub fn parse_func(item: impl CommonFunc, kind: &FuncKind) -> syn::Result<ParsedF
     // - &Self
     // - (... args)
     // Since users can define their own argument names for their method (i.e. _: &Lua), we only collect argument names
-    // for the first 2 required arguments.
+    // for the required arguments.
     // For the other user arguments (if present), we only care about their type for the generics arguments,
     // so we push them into a separate vector.
     // We also store user argument names, to paste them into a scoped function like so:
     // fn func(aa, b) {}  ==>  |(aa, b)| {}
+    //
+    // Required arguments are optional for the user though, since a lot of functions never touch the
+    // Lua state. Instead, we detect whether they're present by their types, and insert them ourselves if not.
 
-    let mut args: Vec<FuncArg> = Vec::new();
-    let req_arg_count = match kind {
-        FuncKind::Method | FuncKind::MethodMut => 2, // Lua + Self
-        FuncKind::Meta => 1,                         // Lua
-        FuncKind::Func => 1,                         // Lua
+    //
+    // Methods can also take a real `self` receiver, which is always the first argument in Rust. It still goes
+    // after the Lua state in bindings, under the `this` name.
+    let mut inputs = info.args.into_iter().peekable();
+    let receiver = match inputs.next_if(|arg| matches!(arg, FnArg::Receiver(_))) {
+        Some(FnArg::Receiver(receiver)) => {
+            if !kind.takes_this() {
+                return Err(syn_error(
+                    receiver,
+                    "Only methods, fields and meta functions can take `self`",
+                ));
+            }
+            Some((SelfReceiver::parse(&receiver)?, receiver.self_token.span))
+        }
+        _ => None,
     };
-    for (ind, inp_ty) in info.args.into_iter().enumerate() {
-        let is_required = ind < req_arg_count;
 
-        let (pat, ty) = match inp_ty {
-            FnArg::Receiver(_) => return Err(syn_error(inp_ty, "Can't contain the self argument")),
-            FnArg::Typed(ty) => (ty.pat, ty.ty),
-        };
+    let mut user_args = inputs
+        .map(|inp_ty| match inp_ty {
+            FnArg::Receiver(_) => Err(syn_error(inp_ty, "Can't contain the self argument")),
+            FnArg::Typed(ty) => FuncArg::from_typed(ty),
+        })
+        .collect::<syn::Result<Vec<FuncArg>>>()?
+        .into_iter()
+        .peekable();
+
+    let mut args: Vec<FuncArg> = Vec::new();
+
+    // The Lua argument always comes first
+    match user_args.next_if(|arg| is_lua_ty(&arg.ty)) {
+        Some(arg) => args.push(FuncArg {
+            required: true,
+            ..arg
+        }),
+        None => {
+            // Async functions take an owned Lua instance, since their futures have to be 'static
+            let lua_ty = if is_async {
+                parse_quote! { ::mlua::Lua }
+            } else {
+                parse_quote! { &::mlua::Lua }
+            };
+            args.push(FuncArg::inserted(lua_ty));
+        }
+    }
 
+    // Then goes `&Self`/`&mut Self` for methods
+    if let Some((receiver, span)) = receiver {
+        let this = Ident::new("this", span);
         args.push(FuncArg {
-            name: *pat,
-            ty: *ty,
-            required: is_required,
+            name: parse_quote! { #this },
+            inserted: false,
+            ..FuncArg::inserted(receiver.this_ty())
         });
+    } else if kind.takes_this() {
+        match user_args.next_if(|arg| is_this_ty(&arg.ty)) {
+            Some(arg) => args.push(FuncArg {
+                required: true,
+                ..arg
+            }),
+            None => args.push(FuncArg::inserted(Type::Infer(parse_quote! { _ }))),
+        }
     }
 
-    if args.len() < req_arg_count {
-        let args_fmt = match kind {
-            FuncKind::Func => "&Lua",
-            FuncKind::Method => "&Lua, &Self",
-            FuncKind::MethodMut => "&Lua, &mut Self",
-            FuncKind::Meta => "&Lua, &impl FromLua",
-        };
+    args.extend(user_args);
+
+    // Required arguments are always passed by mlua, so they can't have defaults or checks
+    let has_arg_attrs = |arg: &&FuncArg| arg.default.is_some() || !arg.checks.is_empty();
+    if let Some(arg) = args.iter().filter(|arg| arg.required).find(has_arg_attrs) {
         return Err(syn_error(
-            name,
-            format!(
-                "Not enough arguments. It should take {} as its first {} arguments",
-                args_fmt, req_arg_count
-            ),
+            &arg.name,
+            "Only Lua arguments can have default values and checks (not `&Lua` or `&Self`)",
         ));
     }
 
+    // Variadic arguments consume all the remaining Lua values, so nothing can come after them
+    if let Some(pos) = args.iter().position(|arg| is_variadic_ty(&arg.ty)) {
+        if pos + 1 != args.len() {
+            return Err(syn_error(
+                &args[pos].ty,
+                "Variadic arguments have to be the last argument of a function",
+            ));
+        }
+    }
+
+    // Bodies of methods with receivers are pasted into closures, where `self` doesn't exist
+    let receiver = receiver.map(|(receiver, _)| receiver);
+    let block = match receiver {
+        Some(_) => syn::parse2::<Block>(replace_self(block.into_token_stream()))?,
+        None => block,
+    };
+
     Ok(ParsedFunc {
         name,
+        naming: ItemName::default(),
         bindgen_ignore,
+        is_async,
+        keep: false,
+        table_args: false,
         visibility,
         block,
+        generics,
+        instantiations: Vec::new(),
         return_ty,
         args,
+        receiver,
+        lua_return,
     })
 }
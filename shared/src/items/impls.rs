@@ -1,16 +1,57 @@
-use syn::{ImplItem, ImplItemFn, ItemImpl, Type};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    visit_mut::VisitMut, Generics, Ident, ImplItem, ImplItemConst, ImplItemFn, ItemImpl, LitStr,
+    Meta, Type, TypePath,
+};
 
-use crate::utils::{contains_attr, syn_error, MLUA_IGNORE_BINDGEN_ATTR};
+use crate::utils::{
+    contains_attr, edit_distance, parse_item_attributes, syn_error, ItemAttribute, ItemAttributes,
+    ItemName, NameRules, RenameRule, MLUA_BINDGEN_ATTR, MLUA_IGNORE_BINDGEN_ATTR,
+};
 
-use super::funcs::{parse_func, FuncKind, ParsedFunc};
+use super::funcs::{
+    find_self_ty, is_variadic_ty, parse_func, result_ok_ty, FuncKind, Instantiation, ParsedFunc,
+    SelfReceiver, SubstituteGenerics, FUNC_ATTRS,
+};
+
+/// Attributes that mark impl functions as Lua fields, methods and functions
+pub const IMPL_FUNC_ATTRS: [&str; 15] = [
+    "method",
+    "method_mut",
+    "func",
+    "function",
+    "function_mut",
+    "meta",
+    "meta_method",
+    "meta_method_mut",
+    "get",
+    "set",
+    "get_function",
+    "set_function",
+    "static_field",
+    "meta_field",
+    "constructor",
+];
 
 /// An enum used to distinguish between setters and getters. When parsing these, the only way to distinguish
 /// them is to look at their attribute. Functions that parse fields can take this enum to apply custom rules:
 ///
 /// For example, a getter can't contain any arguments, while a setter can only contain one.
+#[derive(Clone)]
 pub enum FieldKind {
     Getter,
     Setter,
+    /// A getter that receives the userdata as `AnyUserData` instead of `&Self` (`add_field_function_get`)
+    FunctionGetter,
+    /// A setter that receives the userdata as `AnyUserData` instead of `&mut Self` (`add_field_function_set`)
+    FunctionSetter,
+    /// A field shared by all instances of the type (`add_field`). Its value is computed once, when the type is
+    /// registered
+    Static,
+    /// A field of the userdata metatable (`add_meta_field_with`), i.e. `__name`. Like meta functions, it keeps its
+    /// name
+    Meta,
 }
 
 impl FieldKind {
@@ -19,30 +60,114 @@ impl FieldKind {
     /// Tells the amount of user arguments each field can take
     pub fn user_args(&self) -> usize {
         match self {
-            Self::Getter => 0,
-            Self::Setter => 1,
+            Self::Getter | Self::FunctionGetter | Self::Static | Self::Meta => 0,
+            Self::Setter | Self::FunctionSetter => 1,
         }
     }
+
+    /// Whether the field is accessed through the userdata (`&Self`), rather than through `AnyUserData` or not at all
+    fn takes_this(&self) -> bool {
+        matches!(self, Self::Getter | Self::Setter)
+    }
+
+    /// Whether the field receives the userdata as its first `AnyUserData` argument
+    pub fn takes_userdata(&self) -> bool {
+        matches!(self, Self::FunctionGetter | Self::FunctionSetter)
+    }
 }
 
+#[derive(Clone)]
 pub struct ParsedField {
     pub func: ParsedFunc,
     pub kind: FieldKind,
 }
 
+#[derive(Clone)]
 pub struct ParsedImplFunc {
     pub func: ParsedFunc,
     pub kind: FuncKind,
 }
 
+/// The attribute that exports an associated constant to Lua (i.e. `#[constant] const ZERO: Self = ...;`)
+pub const CONSTANT_ATTR: &str = "constant";
+
+/// An associated constant exported to the class table of its type (`#[constant]`). With `#[constant(field)]`, it's
+/// also a static field of every instance.
+#[derive(Clone)]
+pub struct ParsedConstant {
+    pub ident: Ident,
+    pub ty: Type,
+    /// Only the `name` attribute applies to constants, which otherwise keep their Rust names (see
+    /// [`ParsedConstant::lua_name`])
+    pub naming: ItemName,
+    pub field: bool,
+}
+
+impl ParsedConstant {
+    /// Get the Lua name of the constant. Constants aren't affected by `rename_all`, since they're conventionally
+    /// named the same in Lua (i.e. `Vector.ZERO`)
+    pub fn lua_name(&self) -> String {
+        self.naming.lua_name(&self.ident, NameRules::MEMBER)
+    }
+}
+
+/// The constructor of a userdata type (`#[constructor]`), which makes its class table callable (i.e. `Vector(1, 2)`)
+#[derive(Clone)]
+pub struct ParsedConstructor {
+    pub func: ParsedFunc,
+    /// Whether the constructor can also be called with a single table of named arguments (`#[constructor(table)]`),
+    /// like `Vector{ x = 1, y = 2 }`
+    pub table_form: bool,
+}
+
+/// The base of a userdata type (`extends = Base, via = accessor, via_mut = accessor_mut`). Fields and methods that
+/// the type doesn't declare itself are looked up on its base, which is borrowed from the userdata through the
+/// accessors: `via` (`fn(&Self) -> &Base`) for reading fields, and `via_mut` (`fn(&mut Self) -> &mut Base`) for setting
+/// them and calling methods
+#[derive(Clone)]
+pub struct ParsedBase {
+    pub ty: Type,
+    pub via: syn::Path,
+    pub via_mut: syn::Path,
+}
+
+#[derive(Clone)]
 pub struct ParsedImpl {
     /// Impl blocks don't contain Ident tokens, but rather type
     pub name: Type,
+    /// Naming attributes of the impl type (see [`ParsedImpl::lua_name`])
+    pub naming: ItemName,
+    /// The naming convention of the impl's fields, functions and methods. Meta functions are left as they are,
+    /// since Lua expects their exact names
+    pub rename_all: Option<RenameRule>,
     pub bindgen_ignore: bool,
+    /// Whether the annotated functions are kept as associated functions (see [`ParsedFunc::keep`])
+    pub keep: bool,
+    /// The name of this part, if the impl block only contributes to the main impl block of its type
+    pub partial: Option<Ident>,
+    /// Parts of the type declared in other impl blocks (see [`ParsedImpl::partial`])
+    pub parts: Vec<Ident>,
+    /// Type parameters of a generic impl block. The type implements `UserData` for all of them, but modules need
+    /// concrete types to register it (see [`ParsedImpl::instantiations`])
+    pub generics: Generics,
+    /// Explicit instantiations of a generic impl block, each registered as a separate Lua type
+    pub instantiations: Vec<Instantiation>,
     pub fields: Vec<ParsedField>,
     pub funcs: Vec<ParsedImplFunc>,
     pub methods: Vec<ParsedImplFunc>,
     pub meta_funcs: Vec<ParsedImplFunc>,
+    pub constants: Vec<ParsedConstant>,
+    /// Boxed, since constructors are rare and impls are stored along with smaller module items
+    pub constructor: Option<Box<ParsedConstructor>>,
+    /// The type this one extends (see [`ParsedBase`])
+    pub base: Option<Box<ParsedBase>>,
+    /// Whether modules export the type as a plain table of its functions, constants and constructor, instead of a
+    /// userdata proxy of the type (`lua.create_proxy`), which has them registered on the type itself
+    pub as_table: bool,
+    /// The foreign type this impl block binds (`remote = glam::Vec3`). Orphan rules don't allow implementing
+    /// `UserData` for it, so its items are registered with `lua.register_userdata_type` instead, and the impl type
+    /// only holds them (`Self` in their signatures is the foreign type)
+    pub remote: Option<Box<Type>>,
 }
 
 impl ParsedImpl {
@@ -50,25 +175,572 @@ impl ParsedImpl {
     pub fn from_ty(name: Type) -> Self {
         Self {
             name,
+            naming: ItemName::default(),
+            rename_all: None,
             bindgen_ignore: false,
+            keep: false,
+            partial: None,
+            parts: Vec::new(),
+            generics: Generics::default(),
+            instantiations: Vec::new(),
             fields: Vec::new(),
             funcs: Vec::new(),
             methods: Vec::new(),
             meta_funcs: Vec::new(),
+            constants: Vec::new(),
+            constructor: None,
+            base: None,
+            as_table: false,
+            remote: None,
+        }
+    }
+
+    /// Apply impl-level [`mlua_bindgen`] attributes (i.e. `#[mlua_bindgen(keep)]`) to this impl and its functions
+    pub fn apply_attrs(&mut self, attrs: ItemAttributes) -> syn::Result<()> {
+        let mut derived_meta = Vec::new();
+        let (mut base_ty, mut via, mut via_mut) = (None, None, None);
+        for attr in attrs.0 {
+            match attr {
+                ItemAttribute::Keep => self.keep = true,
+                ItemAttribute::AsTable => self.as_table = true,
+                ItemAttribute::Remote(ty) => self.remote = Some(ty),
+                ItemAttribute::Extends(ty) => base_ty = Some(ty),
+                ItemAttribute::Via(path) => via = Some(path),
+                ItemAttribute::ViaMut(path) => via_mut = Some(path),
+                ItemAttribute::DeriveMeta(metas) => derived_meta.extend(metas),
+                ItemAttribute::RenameAll(rule) => self.rename_all = Some(rule),
+                ItemAttribute::Partial(part) => self.partial = Some(part),
+                ItemAttribute::Instantiate(instances) => self.instantiations.extend(instances),
+                ItemAttribute::Includes(paths) => {
+                    for path in paths {
+                        match path.get_ident() {
+                            Some(part) if !self.parts.contains(part) => self.parts.push(part.clone()),
+                            Some(_) => return Err(syn_error(path, "Parts can't be repeatedly added")),
+                            None => {
+                                return Err(syn_error(
+                                    path,
+                                    "Impl blocks include their parts by name (i.e. `include = [physics]`)",
+                                ))
+                            }
+                        }
+                    }
+                }
+                attr if self.naming.apply_attr(&attr) => {}
+                _ => {
+                    return Err(syn_error(
+                        &self.name,
+                        "Only the `keep`, `name`, `preserve`, `rename_all`, `partial`, `include`, `instantiate`, `meta`, `extends`, `via`, `via_mut`, `as_table` and `remote` attributes can be used on impl blocks",
+                    ))
+                }
+            }
+        }
+        self.base = match (base_ty, via, via_mut) {
+            (Some(ty), Some(via), Some(via_mut)) => Some(Box::new(ParsedBase {
+                ty: *ty,
+                via,
+                via_mut,
+            })),
+            (Some(ty), _, _) => {
+                return Err(syn_error(
+                    ty,
+                    "`extends` needs both accessors of the base (i.e. `via = Sprite::node, via_mut = Sprite::node_mut`)",
+                ))
+            }
+            (None, Some(via), _) | (None, None, Some(via)) => {
+                return Err(syn_error(
+                    via,
+                    "`via` and `via_mut` can only be used along with `extends`",
+                ))
+            }
+            (None, None, None) => None,
+        };
+
+        // The type is named (and registered) by its main impl block, which can't be a part itself
+        if let Some(part) = &self.partial {
+            if self.naming.rename.is_some()
+                || self.naming.preserve
+                || !self.parts.is_empty()
+                || !self.instantiations.is_empty()
+                || self.as_table
+            {
+                return Err(syn_error(
+                    part,
+                    "Parts can't be named, instantiated, exported as tables or include other parts, since their type is declared by its main impl block",
+                ));
+            }
+            if let Some(constructor) = &self.constructor {
+                return Err(syn_error(
+                    &constructor.func.name,
+                    "Constructors have to be declared in the main impl block of the type",
+                ));
+            }
+            if let Some(base) = &self.base {
+                return Err(syn_error(
+                    &base.ty,
+                    "Only the main impl block of the type can extend another type",
+                ));
+            }
+        }
+        self.check_instantiations()?;
+        self.check_base()?;
+        self.check_remote(&derived_meta)?;
+        self.check_constructor()?;
+
+        let keep = self.keep;
+        let fields = self.fields.iter_mut().map(|field| &mut field.func);
+        let funcs = self
+            .funcs
+            .iter_mut()
+            .chain(self.methods.iter_mut())
+            .chain(self.meta_funcs.iter_mut())
+            .map(|impl_func| &mut impl_func.func);
+        let constructor = self
+            .constructor
+            .iter_mut()
+            .map(|constructor| &mut constructor.func);
+        for func in fields.chain(funcs).chain(constructor) {
+            func.keep = keep;
+        }
+
+        // Derived metamethods don't exist in Rust, so they're never kept
+        for meta in derived_meta {
+            let meta_func = parse_impl_func(derived_meta_func(&meta)?, FuncKind::Meta)?;
+            if self
+                .meta_funcs
+                .iter()
+                .any(|impl_func| impl_func.func.name == meta_func.func.name)
+            {
+                return Err(syn_error(
+                    meta,
+                    format!(
+                        "`{}` is already declared in the impl block",
+                        meta_func.func.name
+                    ),
+                ));
+            }
+            self.meta_funcs.push(meta_func);
+        }
+        Ok(())
+    }
+
+    /// Get the Lua name of the impl type under the naming rules of its module. Types are named by the last
+    /// segment of their path (i.e. `math::LuaVector` is `Vector`), and remote types by the path of the foreign type
+    pub fn lua_name(&self, rules: NameRules) -> String {
+        match self.remote.as_deref().unwrap_or(&self.name) {
+            Type::Path(ty_path) if ty_path.qself.is_none() => match ty_path.path.segments.last() {
+                Some(segment) => self.naming.lua_name(&segment.ident, rules),
+                None => self.name.to_token_stream().to_string(),
+            },
+            ty => match &self.naming.rename {
+                Some(rename) => rename.clone(),
+                None => ty.to_token_stream().to_string(),
+            },
+        }
+    }
+
+    /// The naming rules of the impl's fields, functions and methods
+    pub fn member_rules(&self) -> NameRules {
+        NameRules {
+            rename_all: self.rename_all,
+            ..NameRules::MEMBER
+        }
+    }
+
+    /// The last identifier of the impl type (i.e. `Handle` in `assets::Handle<T>`)
+    pub fn ty_ident(&self) -> Option<&Ident> {
+        match &self.name {
+            Type::Path(ty_path) if ty_path.qself.is_none() => {
+                ty_path.path.segments.last().map(|segment| &segment.ident)
+            }
+            _ => None,
         }
     }
+
+    /// Whether the impl block has any type parameters
+    pub fn is_generic(&self) -> bool {
+        self.generics.type_params().next().is_some()
+    }
+
+    /// Extended types look up the members of their base through `__index` and `__newindex`, so they can't declare
+    /// them on their own
+    fn check_base(&self) -> syn::Result<()> {
+        if self.base.is_none() {
+            return Ok(());
+        }
+        let lookup = self.meta_funcs.iter().find(|impl_func| {
+            let name = &impl_func.func.name;
+            name == "__index" || name == "__newindex"
+        });
+        match lookup {
+            Some(impl_func) => Err(syn_error(
+                &impl_func.func.name,
+                format!(
+                    "`{}` can't be declared by extended types, since it looks up the members of their base",
+                    impl_func.func.name
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Remote types are registered as a whole by their impl block, and their bindings receive the foreign type
+    /// instead of `Self`. mlua only makes proxies of `UserData` types, so they're always exported as tables.
+    fn check_remote(&mut self, derived_meta: &[Ident]) -> syn::Result<()> {
+        let remote = match &self.remote {
+            Some(remote) => remote.clone(),
+            None => return Ok(()),
+        };
+        if self.partial.is_some() || !self.parts.is_empty() {
+            return Err(syn_error(remote, "Remote types can't be split into parts"));
+        }
+        if self.is_generic() {
+            return Err(syn_error(remote, "Remote impl blocks can't be generic"));
+        }
+        if self.base.is_some() {
+            return Err(syn_error(remote, "Remote types can't extend other types"));
+        }
+        if self.keep {
+            return Err(syn_error(
+                remote,
+                "Remote impl blocks can't be kept, since their methods receive the remote type instead of `Self`",
+            ));
+        }
+        // Derived operators take their operands by value, which requires `FromLua`
+        if let Some(meta) = derived_meta
+            .iter()
+            .find(|meta| *meta != "tostring" && *meta != "len")
+        {
+            return Err(syn_error(
+                meta,
+                "Remote types can only derive `tostring` and `len`, since their operands can't be converted from Lua",
+            ));
+        }
+        self.as_table = true;
+
+        // The impl type only holds the bindings, so `Self` in their signatures is the remote type, like their `self`.
+        // Bodies are pasted as they are though, where `Self` would still be the impl type
+        let (name, remote_ty) = (self.name.to_token_stream(), remote.to_token_stream());
+        let remote_self = [(Ident::new("Self", proc_macro2::Span::call_site()), *remote)];
+        let funcs = self
+            .fields
+            .iter_mut()
+            .map(|field| &mut field.func)
+            .chain(self.funcs.iter_mut().map(|impl_func| &mut impl_func.func))
+            .chain(self.methods.iter_mut().map(|impl_func| &mut impl_func.func))
+            .chain(
+                self.meta_funcs
+                    .iter_mut()
+                    .map(|impl_func| &mut impl_func.func),
+            )
+            .chain(
+                self.constructor
+                    .iter_mut()
+                    .map(|constructor| &mut constructor.func),
+            );
+        for func in funcs {
+            if let Some(self_ty) = find_self_ty(func.block.to_token_stream()) {
+                return Err(syn_error(
+                    self_ty,
+                    format!("`Self` is `{name}` in the bodies of remote impl blocks, so the remote type has to be named instead (i.e. `{remote_ty}`)"),
+                ));
+            }
+            func.substitute(&remote_self);
+        }
+        Ok(())
+    }
+
+    /// The constructor of a proxy is the `__call` metamethod of the type itself, so the type can't declare it
+    fn check_constructor(&self) -> syn::Result<()> {
+        if self.as_table || self.constructor.is_none() {
+            return Ok(());
+        }
+        match self
+            .meta_funcs
+            .iter()
+            .find(|impl_func| impl_func.func.name == "__call")
+        {
+            Some(impl_func) => Err(syn_error(
+                &impl_func.func.name,
+                "`__call` is the constructor of the type, unless it's exported as a table (`as_table`)",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn check_instantiations(&self) -> syn::Result<()> {
+        if self.instantiations.is_empty() {
+            return Ok(());
+        }
+        if !self.is_generic() {
+            return Err(syn_error(
+                &self.name,
+                "Only generic impl blocks can be instantiated",
+            ));
+        }
+        if let Some(param) = self.generics.const_params().next() {
+            return Err(syn_error(
+                param,
+                "Impl blocks with const parameters can't be instantiated",
+            ));
+        }
+        let ty_ident = match self.ty_ident() {
+            Some(ident) => ident,
+            None => {
+                return Err(syn_error(
+                    &self.name,
+                    "Only named types can be instantiated",
+                ))
+            }
+        };
+        Instantiation::check_list(&self.instantiations, &self.generics, ty_ident, |instance| {
+            self.instance_name(instance, NameRules::ITEM)
+        })
+    }
+
+    /// Get the Lua name of an instance. Named instances are named by their own name instead of the impl type
+    pub fn instance_name(&self, instance: &Instantiation, rules: NameRules) -> String {
+        match &instance.name {
+            Some(name) => ItemName {
+                rename: None,
+                preserve: self.naming.preserve,
+            }
+            .lua_name(name, rules),
+            None => self.lua_name(rules),
+        }
+    }
+
+    /// Get the concrete type of an instance (i.e. `Handle<Mesh>`)
+    pub fn instance_ty(&self, instance: &Instantiation) -> Type {
+        let mut ty = self.name.clone();
+        SubstituteGenerics(&instance.args).visit_type_mut(&mut ty);
+        ty
+    }
+
+    /// Create a concrete copy of this impl block, with all type parameters substituted by the instance's types.
+    /// Named instances are declared as separate types, so the copy is named by the instance.
+    pub fn instantiate(&self, instance: &Instantiation) -> Self {
+        let mut parsed = self.clone();
+        let fields = parsed.fields.iter_mut().map(|field| &mut field.func);
+        let funcs = parsed
+            .funcs
+            .iter_mut()
+            .chain(parsed.methods.iter_mut())
+            .chain(parsed.meta_funcs.iter_mut())
+            .map(|impl_func| &mut impl_func.func);
+        let constructor = parsed
+            .constructor
+            .iter_mut()
+            .map(|constructor| &mut constructor.func);
+        for func in fields.chain(funcs).chain(constructor) {
+            func.substitute(&instance.args);
+        }
+        for constant in parsed.constants.iter_mut() {
+            SubstituteGenerics(&instance.args).visit_type_mut(&mut constant.ty);
+        }
+        if let Some(base) = parsed.base.as_mut() {
+            SubstituteGenerics(&instance.args).visit_type_mut(&mut base.ty);
+        }
+
+        parsed.name = self.instance_ty(instance);
+        if let Some(name) = &instance.name {
+            parsed.name = Type::Path(TypePath {
+                qself: None,
+                path: name.clone().into(),
+            });
+            parsed.naming.rename = None;
+        }
+        parsed.generics = Generics::default();
+        parsed.instantiations = Vec::new();
+        parsed
+    }
 }
 
-/// Parse an impl block and its inner functions into a [`ParsedImpl`]
+/// Metamethods that can be derived from the Rust traits of a type (see [`derived_meta_func`])
+pub const DERIVED_META: [&str; 11] = [
+    "add", "sub", "mul", "div", "rem", "unm", "eq", "lt", "le", "tostring", "len",
+];
+
+/// Build the meta function of a derived metamethod (i.e. `add` is `fn __add(a: Self, b: Self) -> Self`). Operators
+/// take their operands by value (so the type has to implement `FromLua`), and return `Self`, while `tostring` uses
+/// `Display`, and `len` calls the `len` method of the type.
+fn derived_meta_func(meta: &Ident) -> syn::Result<ImplItemFn> {
+    let name = Ident::new(&format!("__{meta}"), meta.span());
+    let binary_op = |op: TokenStream2| {
+        quote_spanned! {meta.span()=>
+            fn #name(a: Self, b: Self) -> Self {
+                a #op b
+            }
+        }
+    };
+    let comparison = |op: TokenStream2| {
+        quote_spanned! {meta.span()=>
+            fn #name(a: Self, b: Self) -> bool {
+                a #op b
+            }
+        }
+    };
+    let tokens = match meta.to_string().as_str() {
+        "add" => binary_op(quote! { + }),
+        "sub" => binary_op(quote! { - }),
+        "mul" => binary_op(quote! { * }),
+        "div" => binary_op(quote! { / }),
+        "rem" => binary_op(quote! { % }),
+        "eq" => comparison(quote! { == }),
+        "lt" => comparison(quote! { < }),
+        "le" => comparison(quote! { <= }),
+        "unm" => quote_spanned! {meta.span()=>
+            fn #name(value: Self) -> Self {
+                -value
+            }
+        },
+        "tostring" => quote_spanned! {meta.span()=>
+            fn #name(&self) -> String {
+                ::std::string::ToString::to_string(self)
+            }
+        },
+        "len" => quote_spanned! {meta.span()=>
+            fn #name(&self) -> usize {
+                self.len()
+            }
+        },
+        _ => {
+            return Err(syn_error(
+                meta,
+                format!(
+                    "Unknown metamethod. Only {} can be derived",
+                    DERIVED_META.map(|name| format!("`{name}`")).join(", ")
+                ),
+            ))
+        }
+    };
+
+    syn::parse2::<ImplItemFn>(tokens)
+}
+
+/// Metamethods mlua can register on userdata (the names of `mlua::MetaMethod`), with the exact amount of operands
+/// Lua calls them with (the userdata included). `None` means any amount.
+///
+/// Some of them only exist in specific Lua versions (i.e. `__close` in Lua 5.4, or `__iter` in Luau)
+pub const META_METHODS: [(&str, Option<usize>); 27] = [
+    ("__add", Some(2)),
+    ("__sub", Some(2)),
+    ("__mul", Some(2)),
+    ("__div", Some(2)),
+    ("__mod", Some(2)),
+    ("__pow", Some(2)),
+    ("__unm", Some(1)),
+    ("__idiv", Some(2)),
+    ("__band", Some(2)),
+    ("__bor", Some(2)),
+    ("__bxor", Some(2)),
+    ("__bnot", Some(1)),
+    ("__shl", Some(2)),
+    ("__shr", Some(2)),
+    ("__concat", Some(2)),
+    ("__len", Some(1)),
+    ("__eq", Some(2)),
+    ("__lt", Some(2)),
+    ("__le", Some(2)),
+    ("__index", Some(2)),
+    ("__newindex", Some(3)),
+    ("__call", None),
+    ("__tostring", Some(1)),
+    ("__pairs", Some(1)),
+    ("__ipairs", Some(1)),
+    ("__iter", Some(1)),
+    ("__close", None),
+];
+
+/// Metamethods that mlua manages itself, and refuses to register
+pub const RESERVED_META_METHODS: [&str; 2] = ["__gc", "__metatable"];
+
+/// Check that a meta function is a known metamethod, that takes as many operands as Lua passes it. Comparison
+/// metamethods also have to return `bool`, since Lua converts their results to booleans anyway.
+fn check_meta_func(func: &ParsedFunc, kind: FuncKind) -> syn::Result<()> {
+    let name = func.lua_name(NameRules::MEMBER);
+    if RESERVED_META_METHODS.contains(&name.as_str()) {
+        return Err(syn_error(
+            &func.name,
+            format!("`{name}` is reserved by mlua, so it can't be registered"),
+        ));
+    }
+
+    let operands = match META_METHODS.iter().find(|(meta, _)| *meta == name) {
+        Some((_, operands)) => *operands,
+        None => {
+            let suggestion = META_METHODS
+                .iter()
+                .map(|(meta, _)| (edit_distance(&name, meta), meta))
+                .filter(|(distance, _)| *distance <= 2)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, meta)| format!(". Did you mean `{meta}`?"))
+                .unwrap_or_default();
+            return Err(syn_error(
+                &func.name,
+                format!("`{name}` is not a Lua metamethod{suggestion}"),
+            ));
+        }
+    };
+
+    // Variadic arguments take any amount of operands
+    let has_variadic = func.args.iter().any(|arg| is_variadic_ty(&arg.ty));
+    let count = func.user_arg_count() + kind.takes_this() as usize;
+    if let Some(operands) = operands.filter(|operands| !has_variadic && *operands != count) {
+        return Err(syn_error(
+            &func.name,
+            format!(
+                "Lua calls `{name}` with {operands} operand{} (the userdata included), but it takes {count}",
+                if operands == 1 { "" } else { "s" }
+            ),
+        ));
+    }
+
+    let ret_ty = result_ok_ty(&func.return_ty).unwrap_or(&func.return_ty);
+    let returns_bool = matches!(ret_ty, Type::Path(ty_path) if ty_path.path.is_ident("bool"));
+    if matches!(name.as_str(), "__eq" | "__lt" | "__le") && !returns_bool {
+        return Err(syn_error(
+            &func.return_ty,
+            format!("`{name}` has to return `bool`"),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether an impl function is exported to Lua (i.e. has the `#[method]` attribute). The rest are left as plain
+/// Rust functions.
+pub fn is_lua_impl_fn(impl_fn: &ImplItemFn) -> bool {
+    IMPL_FUNC_ATTRS
+        .iter()
+        .any(|name| contains_attr(&impl_fn.attrs, name))
+}
+
+/// Parse an impl block and its inner functions into a [`ParsedImpl`]. Functions without Lua attributes, as well as
+/// other impl items (i.e. constants), are skipped.
 pub fn parse_impl(input: ItemImpl) -> syn::Result<ParsedImpl> {
     let name = input.self_ty;
+    let generics = input.generics;
     let bindgen_ignore = contains_attr(&input.attrs, MLUA_IGNORE_BINDGEN_ATTR);
     let mut fields: Vec<ParsedField> = Vec::new();
     let mut methods: Vec<ParsedImplFunc> = Vec::new();
     let mut funcs: Vec<ParsedImplFunc> = Vec::new();
     let mut meta_funcs: Vec<ParsedImplFunc> = Vec::new();
+    let mut constants: Vec<ParsedConstant> = Vec::new();
+    let mut constructor: Option<Box<ParsedConstructor>> = None;
+
+    // mlua requires userdata to be `'static`
+    if let Some(lifetime) = generics.lifetimes().next() {
+        return Err(syn_error(
+            lifetime,
+            "Userdata types can't have lifetime parameters",
+        ));
+    }
 
     for impl_item in input.items {
+        if let ImplItem::Const(impl_const) = &impl_item {
+            if contains_attr(&impl_const.attrs, CONSTANT_ATTR) {
+                constants.push(parse_constant(impl_const)?);
+            }
+        }
         if let ImplItem::Fn(impl_fn) = impl_item {
             if contains_attr(&impl_fn.attrs, "method") {
                 methods.push(parse_impl_func(impl_fn, FuncKind::Method)?);
@@ -76,34 +748,187 @@ pub fn parse_impl(input: ItemImpl) -> syn::Result<ParsedImpl> {
                 methods.push(parse_impl_func(impl_fn, FuncKind::MethodMut)?);
             } else if contains_attr(&impl_fn.attrs, "func") {
                 funcs.push(parse_impl_func(impl_fn, FuncKind::Func)?);
+            } else if contains_attr(&impl_fn.attrs, "function") {
+                methods.push(parse_impl_func(impl_fn, FuncKind::Function)?);
+            } else if contains_attr(&impl_fn.attrs, "function_mut") {
+                methods.push(parse_impl_func(impl_fn, FuncKind::FunctionMut)?);
             } else if contains_attr(&impl_fn.attrs, "meta") {
                 meta_funcs.push(parse_impl_func(impl_fn, FuncKind::Meta)?);
+            } else if contains_attr(&impl_fn.attrs, "meta_method") {
+                meta_funcs.push(parse_impl_func(impl_fn, FuncKind::MetaMethod)?);
+            } else if contains_attr(&impl_fn.attrs, "meta_method_mut") {
+                meta_funcs.push(parse_impl_func(impl_fn, FuncKind::MetaMethodMut)?);
             } else if contains_attr(&impl_fn.attrs, "get") {
                 fields.push(parse_field(impl_fn, FieldKind::Getter)?);
             } else if contains_attr(&impl_fn.attrs, "set") {
                 fields.push(parse_field(impl_fn, FieldKind::Setter)?);
-            } else {
-                return Err(syn_error(impl_fn, "No attributes? If that's intentional - you should move this function to a normal impl block, since this macro ignores non-attributed functions"));
+            } else if contains_attr(&impl_fn.attrs, "get_function") {
+                fields.push(parse_field(impl_fn, FieldKind::FunctionGetter)?);
+            } else if contains_attr(&impl_fn.attrs, "set_function") {
+                fields.push(parse_field(impl_fn, FieldKind::FunctionSetter)?);
+            } else if contains_attr(&impl_fn.attrs, "static_field") {
+                fields.push(parse_field(impl_fn, FieldKind::Static)?);
+            } else if contains_attr(&impl_fn.attrs, "meta_field") {
+                fields.push(parse_field(impl_fn, FieldKind::Meta)?);
+            } else if contains_attr(&impl_fn.attrs, "constructor") {
+                if constructor.is_some() {
+                    return Err(syn_error(
+                        &impl_fn.sig.ident,
+                        "Impl blocks can only have one constructor",
+                    ));
+                }
+                constructor = Some(Box::new(parse_constructor(impl_fn)?));
+            } else if let Some(attr) = impl_fn.attrs.iter().find(|attr| {
+                attr.path().is_ident(MLUA_BINDGEN_ATTR)
+                    || FUNC_ATTRS.iter().any(|name| attr.path().is_ident(name))
+            }) {
+                // Functions without Lua attributes are left as plain Rust functions, where Lua-specific
+                // attributes don't make sense
+                return Err(syn_error(
+                    attr,
+                    "This attribute only works on Lua functions (i.e. marked with `#[method]` or `#[func]`)",
+                ));
             }
         }
     }
 
     Ok(ParsedImpl {
         name: *name,
+        naming: ItemName::default(),
+        rename_all: None,
         bindgen_ignore,
+        keep: false,
+        partial: None,
+        parts: Vec::new(),
+        generics,
+        instantiations: Vec::new(),
         fields,
         methods,
         funcs,
         meta_funcs,
+        constants,
+        constructor,
+        base: None,
+        as_table: false,
+        remote: None,
+    })
+}
+
+/// Parse an associated constant with the [`CONSTANT_ATTR`] attribute (which can take the `field` option)
+fn parse_constant(input: &ImplItemConst) -> syn::Result<ParsedConstant> {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident(CONSTANT_ATTR))
+        .expect("Constants have the constant attribute");
+    let field = match &attr.meta {
+        Meta::List(_) => {
+            let option = attr.parse_args::<Ident>()?;
+            if option != "field" {
+                return Err(syn_error(
+                    option,
+                    "Unknown constant option. Only `field` can be used",
+                ));
+            }
+            true
+        }
+        _ => false,
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn_error(
+            &input.generics,
+            "Generic constants can't be exported to Lua",
+        ));
+    }
+
+    let mut naming = ItemName::default();
+    for attr in parse_item_attributes(&input.attrs)?.0 {
+        match attr {
+            ItemAttribute::Name(_) => {
+                naming.apply_attr(&attr);
+            }
+            _ => {
+                return Err(syn_error(
+                    &input.ident,
+                    "Only the `name` attribute can be used on constants",
+                ))
+            }
+        }
+    }
+
+    Ok(ParsedConstant {
+        ident: input.ident.clone(),
+        ty: input.ty.clone(),
+        naming,
+        field,
     })
 }
 
 /// Parse a lua [`UserData`] field into a [`ParsedField`]
-pub fn parse_field(input: ImplItemFn, kind: FieldKind) -> syn::Result<ParsedField> {
-    let func = match parse_func(input, &FuncKind::MethodMut) {
-        Ok(func) => func,
-        Err(err) => return Err(err),
+pub fn parse_field(mut input: ImplItemFn, kind: FieldKind) -> syn::Result<ParsedField> {
+    // The Lua type of a getter is the type of its field, so it's declared with `lua_type` instead of `lua_return`
+    let lua_type = match input.attrs.iter().position(|attr| attr.path().is_ident("lua_type")) {
+        Some(pos) => match kind {
+            FieldKind::Getter | FieldKind::FunctionGetter | FieldKind::Static | FieldKind::Meta => {
+                Some(input.attrs.remove(pos).parse_args::<LitStr>()?.value())
+            }
+            FieldKind::Setter | FieldKind::FunctionSetter => {
+                return Err(syn_error(
+                    &input.attrs[pos],
+                    "Setters declare their Lua type on the argument (i.e. `#[lua_type(\"...\")] value: Table`)",
+                ))
+            }
+        },
+        None => None,
     };
+    let attrs = parse_item_attributes(&input.attrs)?;
+    // Only regular getters and setters receive `&Self`, the rest are parsed as plain functions
+    let func_kind = match kind.takes_this() {
+        true => FuncKind::MethodMut,
+        false => FuncKind::Func,
+    };
+    let mut func = parse_func(input, &func_kind)?;
+    if lua_type.is_some() {
+        func.lua_return = lua_type;
+    }
+    for attr in attrs.0 {
+        if !func.naming.apply_attr(&attr) {
+            return Err(syn_error(
+                &func.name,
+                "Only the `name` and `preserve` attributes can be used on getters and setters",
+            ));
+        }
+    }
+
+    // mlua doesn't provide async field accessors
+    if func.is_async {
+        return Err(syn_error(func.name, "Getters and setters can't be async"));
+    }
+
+    // mlua gives getters `&Self` and setters `&mut Self`
+    match (&kind, func.receiver) {
+        (FieldKind::Getter, Some(SelfReceiver::RefMut)) => {
+            return Err(syn_error(
+                func.name,
+                "Getters have to take `&self` (or `self`)",
+            ))
+        }
+        (FieldKind::Setter, Some(SelfReceiver::Ref | SelfReceiver::Value { .. })) => {
+            return Err(syn_error(func.name, "Setters have to take `&mut self`"))
+        }
+        _ => {}
+    }
+
+    // Field functions get the userdata from mlua, right after the Lua state
+    if kind.takes_userdata() {
+        match func.args.get_mut(1) {
+            Some(arg) => arg.required = true,
+            None => return Err(syn_error(
+                func.name,
+                "Field functions have to take the userdata (`AnyUserData`) as their first argument",
+            )),
+        }
+    }
 
     let user_arg_count = func.user_arg_count();
 
@@ -111,10 +936,19 @@ pub fn parse_field(input: ImplItemFn, kind: FieldKind) -> syn::Result<ParsedFiel
     if user_arg_count != kind.user_args() {
         let msg = match kind {
             FieldKind::Getter => {
-                "Getters can't contain more than 2 default arguments (&Lua and &Self)"
+                "Getters can't contain any arguments besides the optional &Lua and &Self"
             }
             FieldKind::Setter => {
-                "Setter have to contain exactly 3 arguments (&Lua, &mut Self and 1 user argument)"
+                "Setters have to contain exactly 1 user argument (besides the optional &Lua and &mut Self)"
+            }
+            FieldKind::FunctionGetter => {
+                "Getter functions can't contain any arguments besides the optional &Lua and the userdata"
+            }
+            FieldKind::FunctionSetter => {
+                "Setter functions have to contain exactly 1 user argument (besides the optional &Lua and the userdata)"
+            }
+            FieldKind::Static | FieldKind::Meta => {
+                "Static and meta fields can't contain any arguments besides the optional &Lua"
             }
         };
         return Err(syn_error(func.name, msg));
@@ -123,8 +957,74 @@ pub fn parse_field(input: ImplItemFn, kind: FieldKind) -> syn::Result<ParsedFiel
     Ok(ParsedField { func, kind })
 }
 
+/// Parse the constructor of a type. Its attribute can take the `table` option (`#[constructor(table)]`)
+pub fn parse_constructor(input: ImplItemFn) -> syn::Result<ParsedConstructor> {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("constructor"))
+        .expect("Constructors have the constructor attribute");
+    let table_form = match &attr.meta {
+        Meta::List(_) => {
+            let option = attr.parse_args::<Ident>()?;
+            if option != "table" {
+                return Err(syn_error(
+                    option,
+                    "Unknown constructor option. Only `table` can be used",
+                ));
+            }
+            true
+        }
+        _ => false,
+    };
+
+    let func = parse_impl_func(input, FuncKind::Func)?.func;
+    if table_form && func.table_args {
+        return Err(syn_error(
+            &func.name,
+            "Constructors with `table_args` only take a table, so the `table` option isn't needed",
+        ));
+    }
+    Ok(ParsedConstructor { func, table_form })
+}
+
 /// Parse a lua [`UserData`] method/function into a [`ParsedImplFunc`]
+///
+/// Impl functions can have their own function-level attributes (i.e. `#[mlua_bindgen(table_args)]`)
 pub fn parse_impl_func(input: ImplItemFn, kind: FuncKind) -> syn::Result<ParsedImplFunc> {
-    let func = parse_func(input, &kind)?;
+    // Meta functions with a receiver are registered as meta methods
+    let kind = match (kind, input.sig.receiver()) {
+        (FuncKind::Meta, Some(receiver)) => match &*receiver.ty {
+            Type::Reference(ty_ref) if ty_ref.mutability.is_some() => FuncKind::MetaMethodMut,
+            _ => FuncKind::MetaMethod,
+        },
+        (kind, _) => kind,
+    };
+    let attrs = parse_item_attributes(&input.attrs)?;
+    let mut func = parse_func(input, &kind)?;
+    func.apply_attrs(attrs)?;
+
+    if matches!(
+        kind,
+        FuncKind::Meta | FuncKind::MetaMethod | FuncKind::MetaMethodMut
+    ) {
+        check_meta_func(&func, kind)?;
+    }
+    if func.keep {
+        return Err(syn_error(
+            &func.name,
+            "The `keep` attribute has to be applied to the whole impl block",
+        ));
+    }
+    // mlua doesn't provide async mutable functions
+    if let (FuncKind::FunctionMut, true) = (kind, func.is_async) {
+        return Err(syn_error(&func.name, "Mutable functions can't be async"));
+    }
+    if !func.instantiations.is_empty() {
+        return Err(syn_error(
+            &func.name,
+            "Only free functions can be instantiated",
+        ));
+    }
     Ok(ParsedImplFunc { func, kind })
 }
@@ -2,3 +2,4 @@ pub mod enums;
 pub mod funcs;
 pub mod impls;
 pub mod mods;
+pub mod structs;
@@ -3,13 +3,15 @@ use std::collections::HashSet;
 use syn::{Ident, Item, ItemMod, Path, Visibility};
 
 use crate::utils::{
-    contains_attr, syn_error, ItemAttribute, ItemAttributes, LastPathIdent, ToIdent, MLUA_BINDGEN_ATTR, MLUA_IGNORE_BINDGEN_ATTR
+    contains_attr, parse_item_attributes, syn_error, ItemAttribute, ItemAttributes, LastPathIdent,
+    NameRules, RenameRule, ToIdent, MLUA_BINDGEN_ATTR, MLUA_IGNORE_BINDGEN_ATTR,
 };
 
 use super::{
     enums::{parse_enum, ParsedEnum},
     funcs::{parse_func, FuncKind, ParsedFunc},
     impls::{parse_impl, ParsedImpl},
+    structs::{parse_struct, ParsedStruct},
 };
 
 pub const MODULE_SUFFIX: &str = "_module";
@@ -20,6 +22,9 @@ pub enum ModuleItem {
     Fn(ParsedFunc),
     Enum(ParsedEnum),
     Impl(ParsedImpl),
+    /// Structs aren't registered by modules themselves (their fields belong to their impl), but bindgen
+    /// needs them to declare the fields
+    Struct(ParsedStruct),
 }
 
 /// Basically a path, but for modules. It simplifies prefix management and other stuff
@@ -75,6 +80,10 @@ impl ModulePath {
 pub struct ParsedModule {
     pub ident: Ident,
     pub ismain: bool,
+    /// Whether the module items keep their Lua prefixes
+    pub preserve: bool,
+    /// The naming convention of the module functions
+    pub rename_all: Option<RenameRule>,
     pub bindgen_ignore: bool,
     pub visibility: Visibility,
     pub includes: Vec<ModulePath>,
@@ -89,11 +98,29 @@ impl ParsedModule {
             match mod_item {
                 ModuleItem::Enum(mod_enum) => !mod_enum.bindgen_ignore,
                 ModuleItem::Fn(mod_fn) => !mod_fn.bindgen_ignore,
-                ModuleItem::Impl(mod_impl) => !mod_impl.bindgen_ignore
+                ModuleItem::Impl(mod_impl) => !mod_impl.bindgen_ignore,
+                ModuleItem::Struct(mod_struct) => !mod_struct.bindgen_ignore,
             }
             // TODO: Clean the impl blocks as well, as they can contain bindgen_ignore items
         });
     }
+
+    /// The naming rules of the module functions
+    pub fn func_rules(&self) -> NameRules {
+        NameRules {
+            remove_prefix: !self.preserve,
+            rename_all: self.rename_all,
+        }
+    }
+
+    /// The naming rules of the module types (impls and enums). Types follow their own convention, so
+    /// `rename_all` doesn't apply to them
+    pub fn type_rules(&self) -> NameRules {
+        NameRules {
+            remove_prefix: !self.preserve,
+            rename_all: None,
+        }
+    }
 }
 
 /// Try parse an ItemMod into a ParsedModule.
@@ -107,6 +134,8 @@ pub fn parse_mod(
 ) -> syn::Result<ParsedModule> {
     let ident = item.ident;
     let mut ismain = false;
+    let mut preserve = false;
+    let mut rename_all = None;
     let visibility = item.vis;
     let mut items: Vec<ModuleItem> = Vec::new();
     let mut includes: Vec<ModulePath> = Vec::new();
@@ -120,11 +149,47 @@ pub fn parse_mod(
         match attr {
             ItemAttribute::Includes(paths) => included = paths,
             ItemAttribute::IsMain => ismain = true,
-            // TODO
-            ItemAttribute::Preserve => {}
+            ItemAttribute::Preserve => preserve = true,
+            ItemAttribute::RenameAll(rule) => rename_all = Some(rule),
+            ItemAttribute::Name(_) => {
+                return Err(syn_error(
+                    ident,
+                    "Modules can't be renamed, since their names are taken from `include` paths",
+                ))
+            }
             // TODO
             ItemAttribute::BindgenIgnore => {}
             ItemAttribute::PostInitFunc(path) => post_init_func = Some(path),
+            ItemAttribute::Keep => {
+                return Err(syn_error(
+                    ident,
+                    "The `keep` attribute can only be used on functions and impl blocks",
+                ))
+            }
+            ItemAttribute::TableArgs => {
+                return Err(syn_error(
+                    ident,
+                    "The `table_args` attribute can only be used on functions",
+                ))
+            }
+            ItemAttribute::Instantiate(_) => {
+                return Err(syn_error(
+                    ident,
+                    "The `instantiate` attribute can only be used on functions",
+                ))
+            }
+            ItemAttribute::Partial(_)
+            | ItemAttribute::DeriveMeta(_)
+            | ItemAttribute::Extends(_)
+            | ItemAttribute::Via(_)
+            | ItemAttribute::ViaMut(_)
+            | ItemAttribute::AsTable
+            | ItemAttribute::Remote(_) => {
+                return Err(syn_error(
+                    ident,
+                    "The `partial`, `meta`, `extends`, `via`, `via_mut`, `as_table` and `remote` attributes can only be used on impl blocks",
+                ))
+            }
         }
     }
 
@@ -155,33 +220,65 @@ pub fn parse_mod(
                     if !contains_attr(&mod_fn.attrs, MLUA_BINDGEN_ATTR) {
                         continue;
                     }
-                    ModuleItem::Fn(if parse_items {
+                    let fn_attrs = parse_item_attributes(&mod_fn.attrs)?;
+                    let mut func = if parse_items {
                         parse_func(mod_fn, &FuncKind::Func)?
                     } else {
-                        ParsedFunc::from_ident(mod_fn.sig.ident)
-                    })
+                        // Module registration still needs to know which mlua API to use for the function
+                        // (and which types to instantiate it with)
+                        let is_async = mod_fn.sig.asyncness.is_some();
+                        let mut func = ParsedFunc::from_ident(mod_fn.sig.ident);
+                        func.is_async = is_async;
+                        func.generics = mod_fn.sig.generics;
+                        func
+                    };
+                    func.apply_attrs(fn_attrs)?;
+
+                    ModuleItem::Fn(func)
                 }
                 Item::Enum(mod_enum) => {
                     if !contains_attr(&mod_enum.attrs, MLUA_BINDGEN_ATTR) {
                         continue;
                     }
 
-                    ModuleItem::Enum(if parse_items {
+                    let enum_attrs = parse_item_attributes(&mod_enum.attrs)?;
+                    let mut parsed_enum = if parse_items {
                         parse_enum(mod_enum)?
                     } else {
                         ParsedEnum::from_ident(mod_enum.ident)
-                    })
+                    };
+                    parsed_enum.apply_attrs(enum_attrs)?;
+
+                    ModuleItem::Enum(parsed_enum)
                 }
                 Item::Impl(mod_impl) => {
                     if !contains_attr(&mod_impl.attrs, MLUA_BINDGEN_ATTR) {
                         continue;
                     }
 
-                    ModuleItem::Impl(if parse_items {
+                    let impl_attrs = parse_item_attributes(&mod_impl.attrs)?;
+                    let mut parsed_impl = if parse_items {
                         parse_impl(mod_impl)?
                     } else {
-                        ParsedImpl::from_ty(*mod_impl.self_ty)
-                    })
+                        // Generic impls are registered by their instances
+                        let mut parsed_impl = ParsedImpl::from_ty(*mod_impl.self_ty);
+                        parsed_impl.generics = mod_impl.generics;
+                        parsed_impl
+                    };
+                    parsed_impl.apply_attrs(impl_attrs)?;
+
+                    ModuleItem::Impl(parsed_impl)
+                }
+                Item::Struct(mod_struct) => {
+                    if !parse_items || !contains_attr(&mod_struct.attrs, MLUA_BINDGEN_ATTR) {
+                        continue;
+                    }
+
+                    let struct_attrs = parse_item_attributes(&mod_struct.attrs)?;
+                    let mut parsed_struct = parse_struct(mod_struct)?;
+                    parsed_struct.apply_attrs(struct_attrs)?;
+
+                    ModuleItem::Struct(parsed_struct)
                 }
                 Item::Mod(mod_mod) => return Err(syn_error(
                     mod_mod,
@@ -196,6 +293,8 @@ pub fn parse_mod(
 
     Ok(ParsedModule {
         ismain,
+        preserve,
+        rename_all,
         bindgen_ignore,
         ident,
         visibility,
@@ -0,0 +1,169 @@
+use syn::{
+    parse::Parse, parse_quote, punctuated::Punctuated, token::Comma, Attribute, Fields, Ident,
+    ImplItemFn, ItemStruct, LitStr, Token,
+};
+
+use crate::utils::{
+    contains_attr, syn_error, ItemAttribute, ItemAttributes, ItemName, NameRules, RenameRule,
+    MLUA_IGNORE_BINDGEN_ATTR,
+};
+
+use super::impls::{parse_field, FieldKind, ParsedField};
+
+/// The attribute that configures how a struct field is exposed to Lua (i.e. `#[lua(get, set)]`)
+pub const FIELD_ATTR: &str = "lua";
+
+/// A single option of the [`FIELD_ATTR`] attribute
+enum FieldOption {
+    Get,
+    Set,
+    Skip,
+    Name(String),
+}
+
+impl Parse for FieldOption {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        if ident == "get" {
+            Ok(Self::Get)
+        } else if ident == "set" {
+            Ok(Self::Set)
+        } else if ident == "skip" {
+            Ok(Self::Skip)
+        } else if ident == "name" {
+            input.parse::<Token![=]>()?;
+            Ok(Self::Name(input.parse::<LitStr>()?.value()))
+        } else {
+            Err(syn_error(
+                ident,
+                "Unknown field option. Only `get`, `set`, `skip` and `name` can be used",
+            ))
+        }
+    }
+}
+
+/// A struct whose fields are exposed to Lua directly. Fields are registered through the same getters and setters
+/// as the ones written by hand in impl blocks, so they're parsed into [`ParsedField`]s.
+pub struct ParsedStruct {
+    pub ident: Ident,
+    pub bindgen_ignore: bool,
+    /// The naming convention of the fields
+    pub rename_all: Option<RenameRule>,
+    pub fields: Vec<ParsedField>,
+}
+
+impl ParsedStruct {
+    /// Apply struct-level [`mlua_bindgen`] attributes (i.e. `#[mlua_bindgen(rename_all = "camelCase")]`)
+    pub fn apply_attrs(&mut self, attrs: ItemAttributes) -> syn::Result<()> {
+        for attr in attrs.0 {
+            match attr {
+                ItemAttribute::RenameAll(rule) => self.rename_all = Some(rule),
+                _ => {
+                    return Err(syn_error(
+                        &self.ident,
+                        "Only the `rename_all` attribute can be used on structs",
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The naming rules of the struct fields
+    pub fn field_rules(&self) -> NameRules {
+        NameRules {
+            rename_all: self.rename_all,
+            ..NameRules::MEMBER
+        }
+    }
+}
+
+/// Remove [`FIELD_ATTR`] attributes from struct fields, since they're only known to the macro
+pub fn remove_field_attrs(fields: &mut Fields) {
+    for field in fields.iter_mut() {
+        field.attrs.retain(|attr| !attr.path().is_ident(FIELD_ATTR));
+    }
+}
+
+/// Parse the [`FIELD_ATTR`] options of a field. Fields without them aren't exposed, just like skipped ones
+fn parse_field_options(attrs: &[Attribute]) -> syn::Result<(bool, bool, ItemName)> {
+    let mut naming = ItemName::default();
+    let attr = match attrs.iter().find(|attr| attr.path().is_ident(FIELD_ATTR)) {
+        Some(attr) => attr,
+        None => return Ok((false, false, naming)),
+    };
+
+    let (mut get, mut set) = (false, false);
+    let options = attr.parse_args_with(Punctuated::<FieldOption, Comma>::parse_terminated)?;
+    for option in options {
+        match option {
+            FieldOption::Get => get = true,
+            FieldOption::Set => set = true,
+            FieldOption::Skip => return Ok((false, false, naming)),
+            FieldOption::Name(name) => naming.rename = Some(name),
+        }
+    }
+    if !get && !set {
+        return Err(syn_error(
+            attr,
+            "Exposed fields need at least a `get` or `set` option (or `skip` to hide them)",
+        ));
+    }
+    Ok((get, set, naming))
+}
+
+/// Parse a struct with named fields into a [`ParsedStruct`]. Every exposed field gets a getter that clones its
+/// value, and (with `set`) a setter that replaces it.
+pub fn parse_struct(item: ItemStruct) -> syn::Result<ParsedStruct> {
+    let ident = item.ident;
+    let bindgen_ignore = contains_attr(&item.attrs, MLUA_IGNORE_BINDGEN_ATTR);
+
+    if !item.generics.params.is_empty() {
+        return Err(syn_error(
+            &item.generics,
+            "Fields of generic structs can't be exposed to Lua",
+        ));
+    }
+    let named = match item.fields {
+        Fields::Named(named) => named,
+        _ => {
+            return Err(syn_error(
+                ident,
+                "Only structs with named fields can expose them to Lua",
+            ))
+        }
+    };
+
+    let mut fields = Vec::new();
+    for field in named.named {
+        let (get, set, naming) = parse_field_options(&field.attrs)?;
+        let (name, ty) = (field.ident.expect("Named fields have idents"), field.ty);
+
+        if get {
+            let getter: ImplItemFn = parse_quote! {
+                fn #name(&self) -> #ty {
+                    ::std::clone::Clone::clone(&self.#name)
+                }
+            };
+            fields.push(parse_field(getter, FieldKind::Getter)?);
+        }
+        if set {
+            let setter: ImplItemFn = parse_quote! {
+                fn #name(&mut self, #name: #ty) {
+                    self.#name = #name;
+                }
+            };
+            fields.push(parse_field(setter, FieldKind::Setter)?);
+        }
+        for field in fields.iter_mut().rev().take(get as usize + set as usize) {
+            field.func.naming = naming.clone();
+        }
+    }
+
+    Ok(ParsedStruct {
+        ident,
+        bindgen_ignore,
+        rename_all: None,
+        fields,
+    })
+}
@@ -2,9 +2,11 @@ use std::fmt::Display;
 
 use proc_macro2::TokenStream as TokenStream2;
 use syn::{
-    parse::Parse, parse2, spanned::Spanned, token::Comma, Attribute, Expr, ExprArray, Ident, Item, ItemEnum, ItemFn, ItemImpl, ItemMod, Token
+    parenthesized, parse::Parse, parse2, punctuated::Punctuated, spanned::Spanned, token::Comma, Attribute, Expr, ExprArray, Ident, Item, ItemEnum, ItemFn, ItemImpl, ItemMod, ItemStruct, LitStr, Meta, Token, Type
 };
 
+use crate::funcs::Instantiation;
+
 pub const MLUA_BINDGEN_ATTR: &str = "mlua_bindgen";
 /// The reason it's not `MLUA_BINDGEN_IGNORE_ATTR`, is that my intellisense constantly recommends my the
 /// default `MLUA_BINDGEN_ATTR`, so I renamed it to emphasize the `IGNORE` part at the start, 
@@ -17,6 +19,7 @@ pub enum ItemKind {
     Fn(ItemFn),
     Mod(ItemMod),
     Enum(ItemEnum),
+    Struct(ItemStruct),
     /// While
     Unsupported(Item),
 }
@@ -31,6 +34,7 @@ pub fn parse_item(input: TokenStream2) -> ItemKind {
         Item::Fn(item) => ItemKind::Fn(item),
         Item::Enum(item) => ItemKind::Enum(item),
         Item::Mod(item) => ItemKind::Mod(item),
+        Item::Struct(item) => ItemKind::Struct(item),
         _ => ItemKind::Unsupported(item),
     }
 }
@@ -53,14 +57,40 @@ pub enum ItemAttribute {
     Includes(Vec<syn::Path>),
     /// An attribute only useful in bindgen, that signifies that this is the main module entrypoint
     IsMain,
-    /// An attribute that tells to keep the original name, without removing its Lua prefix.
+    /// An attribute that tells to keep the original name, without removing its Lua prefix (or applying `rename_all`).
     Preserve,
+    /// Export the item under an explicit Lua name (i.e. `name = "getPosition"`)
+    Name(String),
+    /// Rename all items of a module or an impl block by a naming convention (i.e. `rename_all = "camelCase"`)
+    RenameAll(RenameRule),
     /// Tell the bindgen to ignore this element when generating bindings. Useful when replacing standard
     /// functions like `require`
     BindgenIgnore,
     /// Tells the macro to call a post-init function under provided path before returning a module table.
     /// Useful if you need to manually modify the table.
     PostInitFunc(syn::Path),
+    /// Keep the original function as it is, and generate a separate `{name}_lua` adapter for Lua instead.
+    Keep,
+    /// Take all function arguments from a single Lua table by their names (i.e. `spawn{ x = 1, y = 2 }`)
+    TableArgs,
+    /// Instantiate a generic function with concrete types (i.e. `instantiate(T = i64)`), so it can be
+    /// registered in Lua.
+    Instantiate(Vec<Instantiation>),
+    /// Mark an impl block as a named part of a userdata type (i.e. `partial = physics`), which is included by
+    /// its main impl block (`include = [physics]`)
+    Partial(Ident),
+    /// Derive metamethods of an impl block from the Rust traits of its type (i.e. `meta(add, eq, tostring)`)
+    DeriveMeta(Vec<Ident>),
+    /// Make the userdata type fall back to the fields and methods of another userdata type (i.e. `extends = Node`)
+    Extends(Box<Type>),
+    /// The accessor of the base type of `extends` (i.e. `via = Sprite::node`)
+    Via(syn::Path),
+    /// The mutable accessor of the base type of `extends` (i.e. `via_mut = Sprite::node_mut`)
+    ViaMut(syn::Path),
+    /// Export an impl block's type to modules as a plain table (see `as_table`) instead of a userdata proxy
+    AsTable,
+    /// Bind a type from another crate through the impl block of a local type (i.e. `remote = glam::Vec3`)
+    Remote(Box<Type>),
 }
 
 impl Parse for ItemAttributes {
@@ -120,11 +150,83 @@ impl Parse for ItemAttributes {
                 //? main
 
                 ItemAttribute::IsMain
+            } else if ident == "keep" {
+                //? keep
+
+                ItemAttribute::Keep
+            } else if ident == "as_table" {
+                //? as_table
+
+                ItemAttribute::AsTable
+            } else if ident == "table_args" {
+                //? table_args
+
+                ItemAttribute::TableArgs
+            } else if ident == "instantiate" {
+                //? instantiate(T = i64) or instantiate(int_id(T = i64), num_id(T = f64))
+
+                let content;
+                parenthesized!(content in input);
+                ItemAttribute::Instantiate(Instantiation::parse_list(&content)?)
+            } else if ident == "partial" {
+                //? partial = physics
+
+                input.parse::<Token![=]>()?;
+                ItemAttribute::Partial(input.parse::<Ident>()?)
+            } else if ident == "meta" {
+                //? meta(add, eq, tostring)
+
+                let content;
+                parenthesized!(content in input);
+                let metas = Punctuated::<Ident, Comma>::parse_terminated(&content)?;
+                ItemAttribute::DeriveMeta(metas.into_iter().collect())
+            } else if ident == "extends" {
+                //? extends = Node
+
+                input.parse::<Token![=]>()?;
+                ItemAttribute::Extends(Box::new(input.parse::<Type>()?))
+            } else if ident == "remote" {
+                //? remote = glam::Vec3
+
+                input.parse::<Token![=]>()?;
+                ItemAttribute::Remote(Box::new(input.parse::<Type>()?))
+            } else if ident == "via" || ident == "via_mut" {
+                //? via = Sprite::node or via_mut = Sprite::node_mut
+
+                input.parse::<Token![=]>()?;
+
+                let expr = input.parse::<Expr>()?;
+
+                if let Expr::Path(path) = expr {
+                    match ident == "via" {
+                        true => ItemAttribute::Via(path.path),
+                        false => ItemAttribute::ViaMut(path.path),
+                    }
+                } else {
+                    return Err(syn_error(
+                        expr,
+                        "Expected a function path to the accessor of the base type",
+                    ));
+                }
             } else if ident == "preserve" {
-                return Err(syn::Error::new_spanned(
-                    ident,
-                    "The `preserve` attribute is currently not supported",
-                ));
+                //? preserve
+
+                ItemAttribute::Preserve
+            } else if ident == "name" {
+                //? name = "getPosition"
+
+                input.parse::<Token![=]>()?;
+                let name = input.parse::<LitStr>()?;
+                if name.value().is_empty() {
+                    return Err(syn_error(name, "Lua names can't be empty"));
+                }
+
+                ItemAttribute::Name(name.value())
+            } else if ident == "rename_all" {
+                //? rename_all = "camelCase"
+
+                input.parse::<Token![=]>()?;
+                ItemAttribute::RenameAll(input.parse::<RenameRule>()?)
             } else if ident == "bindgen_ignore" {
                 return Err(syn::Error::new_spanned(
                     ident,
@@ -133,7 +235,7 @@ impl Parse for ItemAttributes {
             } else {
                 return Err(syn::Error::new_spanned(
                     ident,
-                    "Unknown keyword. Only `main`, `preserve` and `include` can be used",
+                    "Unknown keyword. Only `main`, `preserve`, `name`, `rename_all`, `include`, `post_init`, `keep`, `table_args`, `instantiate`, `partial`, `meta`, `extends`, `via`, `via_mut`, `as_table` and `remote` can be used",
                 ));
             };
 
@@ -151,11 +253,173 @@ impl Parse for ItemAttributes {
     }
 }
 
+/// A naming convention applied to all items of a module or an impl block (`rename_all`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenameRule {
+    /// `camelCase`
+    CamelCase,
+    /// `PascalCase`
+    PascalCase,
+    /// `snake_case`
+    SnakeCase,
+}
+
+impl RenameRule {
+    /// Split a name into its lowercase words. Both underscores and case changes separate words, so it works
+    /// for any of the supported conventions (i.e. `get_position` and `getPosition` are both `[get, position]`).
+    /// Acronyms are kept as a single word, unless their last capital starts the next one (`HTTPServer` is
+    /// `[http, server]`)
+    fn words(name: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        for part in name.split('_').filter(|part| !part.is_empty()) {
+            let chars: Vec<char> = part.chars().collect();
+            let mut word = String::new();
+            for (ind, &ch) in chars.iter().enumerate() {
+                // The case is checked on the original characters, since the word is already lowercase
+                let boundary = ch.is_uppercase()
+                    && ind > 0
+                    && (!chars[ind - 1].is_uppercase()
+                        || chars.get(ind + 1).is_some_and(|next| next.is_lowercase()));
+                if boundary {
+                    words.push(std::mem::take(&mut word));
+                }
+                word.extend(ch.to_lowercase());
+            }
+            words.push(word);
+        }
+        words
+    }
+
+    /// Apply the naming convention to a name
+    pub fn apply(&self, name: &str) -> String {
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        };
+
+        let words = Self::words(name);
+        match self {
+            Self::SnakeCase => words.join("_"),
+            Self::PascalCase => words.iter().map(capitalize).collect(),
+            Self::CamelCase => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.clone()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Parse for RenameRule {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let rule = input.parse::<LitStr>()?;
+        match rule.value().as_str() {
+            "camelCase" => Ok(Self::CamelCase),
+            "PascalCase" => Ok(Self::PascalCase),
+            "snake_case" => Ok(Self::SnakeCase),
+            _ => Err(syn_error(
+                rule,
+                "Unknown naming convention. Only `camelCase`, `PascalCase` and `snake_case` are supported",
+            )),
+        }
+    }
+}
+
+/// Rules a parent item (a module or an impl block) applies to the Lua names of its items
+#[derive(Clone, Copy)]
+pub struct NameRules {
+    /// Whether the Lua prefix is removed (see [`remove_lua_prefix`])
+    pub remove_prefix: bool,
+    pub rename_all: Option<RenameRule>,
+}
+
+impl NameRules {
+    /// Module items (functions, impls and enums) lose their Lua prefix
+    pub const ITEM: Self = Self {
+        remove_prefix: true,
+        rename_all: None,
+    };
+    /// Impl block members (fields, functions and methods) keep their Rust names
+    pub const MEMBER: Self = Self {
+        remove_prefix: false,
+        rename_all: None,
+    };
+}
+
+/// Item-level naming attributes (`name = "..."` and `preserve`).
+///
+/// Both the macros and bindgen get Lua names through [`ItemName::lua_name`], so registered keys and
+/// declarations always agree.
+#[derive(Clone, Default)]
+pub struct ItemName {
+    /// An explicit Lua name, which is used as it is
+    pub rename: Option<String>,
+    /// Keep the Rust name as it is, ignoring the rules of the parent item
+    pub preserve: bool,
+}
+
+impl ItemName {
+    /// Apply a naming attribute to this item. Returns `false` if the attribute isn't a naming one
+    pub fn apply_attr(&mut self, attr: &ItemAttribute) -> bool {
+        match attr {
+            ItemAttribute::Name(name) => self.rename = Some(name.clone()),
+            ItemAttribute::Preserve => self.preserve = true,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Get the Lua name of an item. An explicit name always wins, then `preserve`, and only then the rules
+    /// of the parent item (prefix removal first, `rename_all` second)
+    pub fn lua_name(&self, ident: &Ident, rules: NameRules) -> String {
+        if let Some(rename) = &self.rename {
+            return rename.clone();
+        }
+
+        let name = ident.to_string();
+        if self.preserve {
+            return name;
+        }
+        let name = match rules.remove_prefix {
+            true => remove_lua_prefix(name),
+            false => name,
+        };
+        match rules.rename_all {
+            Some(rule) => rule.apply(&name),
+            None => name,
+        }
+    }
+}
+
 /// Parse tokens into [`ItemAttrs`]
 pub fn parse_attributes(input: TokenStream2) -> syn::Result<ItemAttributes> {
     parse2::<ItemAttributes>(input)
 }
 
+/// Find the [`MLUA_BINDGEN_ATTR`] attribute of an item, and parse its arguments into [`ItemAttributes`].
+///
+/// Items without this attribute (or without any arguments) will simply return an empty attribute list
+pub fn parse_item_attributes(attrs: &[Attribute]) -> syn::Result<ItemAttributes> {
+    let attr = match attrs.iter().find(|attr| attr.path().is_ident(MLUA_BINDGEN_ATTR)) {
+        Some(attr) => attr,
+        None => return Ok(ItemAttributes::empty()),
+    };
+
+    match &attr.meta {
+        Meta::List(meta_list) => parse_attributes(meta_list.tokens.clone()),
+        _ => Ok(ItemAttributes::empty()),
+    }
+}
+
 /// Constructs a quick error;
 pub fn syn_error<S, D>(span: S, message: D) -> syn::Error
 where
@@ -216,7 +480,8 @@ impl LastPathIdent for syn::Path {
 ///
 /// # Example
 /// ```
-/// get_lua_prefix("LuaHello") -> [Some(3)]
+/// # use shared::utils::get_lua_prefix;
+/// assert_eq!(get_lua_prefix("LuaHello"), Some(3));
 /// // where 3 --------^  (points to "H")
 /// ```
 ///
@@ -239,6 +504,7 @@ pub fn get_lua_prefix(s: &str) -> Option<usize> {
 ///
 /// # Example
 /// ```
+/// # use shared::utils::remove_lua_prefix;
 /// let string = "LuaType".to_owned();
 /// let no_prefix = remove_lua_prefix(string);
 /// assert_eq!(no_prefix, "Type".to_owned());
@@ -253,9 +519,28 @@ pub fn remove_lua_prefix(s: String) -> String {
     }
 }
 
+/// The Levenshtein distance between two strings: the least amount of single character insertions, removals and
+/// replacements that turn one into the other. It's used to suggest names for typos.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // The distances between the current prefix of `a` and every prefix of `b`
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_ch) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_ch) in b.iter().enumerate() {
+            let replaced = diagonal + (a_ch != *b_ch) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
 #[cfg(test)]
 mod test {
-    use crate::utils::remove_lua_prefix;
+    use crate::utils::{edit_distance, remove_lua_prefix, RenameRule};
 
     use super::get_lua_prefix;
 
@@ -278,4 +563,38 @@ mod test {
             "slua_func".to_owned()
         );
     }
+
+    #[test]
+    fn rename_rules() {
+        assert_eq!(RenameRule::CamelCase.apply("get_position"), "getPosition");
+        assert_eq!(RenameRule::CamelCase.apply("Vector"), "vector");
+        assert_eq!(RenameRule::PascalCase.apply("get_position"), "GetPosition");
+        assert_eq!(RenameRule::PascalCase.apply("getPosition"), "GetPosition");
+        assert_eq!(RenameRule::SnakeCase.apply("getPosition"), "get_position");
+        assert_eq!(RenameRule::CamelCase.apply("to_vec2"), "toVec2");
+        assert_eq!(RenameRule::SnakeCase.apply("len"), "len");
+    }
+
+    #[test]
+    fn rename_acronyms_and_digits() {
+        assert_eq!(RenameRule::SnakeCase.apply("HTTPServer"), "http_server");
+        assert_eq!(RenameRule::SnakeCase.apply("getID"), "get_id");
+        assert_eq!(RenameRule::CamelCase.apply("getID"), "getId");
+        assert_eq!(RenameRule::CamelCase.apply("parse_URL"), "parseUrl");
+        assert_eq!(
+            RenameRule::PascalCase.apply("XMLHttpRequest"),
+            "XmlHttpRequest"
+        );
+        assert_eq!(RenameRule::SnakeCase.apply("vec2Length"), "vec2_length");
+        assert_eq!(RenameRule::CamelCase.apply("to_vec_2d"), "toVec2d");
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("__tostring", "__tostring"), 0);
+        assert_eq!(edit_distance("__tostrng", "__tostring"), 1);
+        assert_eq!(edit_distance("add", "__add"), 2);
+        assert_eq!(edit_distance("__eq", "__le"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
 }
@@ -0,0 +1,247 @@
+//! Runtime helpers used by the code generated with [`mlua_bindgen`](crate::mlua_bindgen). These aren't part of
+//! the public API, and can change at any time.
+//!
+//! This crate doesn't depend on mlua (since mlua requires a specific Lua version feature), so the helpers are
+//! generic over the target error type, which the generated code always sets to `mlua::Error`.
+
+use std::{cell::Cell, error::Error as StdError, marker::PhantomData, sync::Arc};
+
+/// An external error, as stored by `mlua::Error::ExternalError`
+pub type ExternalError = Arc<dyn StdError + Send + Sync>;
+
+/// A binding error waiting to be converted into a Lua error of type `T`.
+///
+/// Bindings can return any error that either implements `Into<mlua::Error>`, or is a [`std::error::Error`].
+/// Since Rust can't choose between these two at the moment (no specialization), we pick the conversion with
+/// autoref-based specialization instead. The conversion is called on `&&&ErrorWrap`, and method resolution prefers
+/// the impls on the outer references: [`ViaInto`] is implemented for `&&ErrorWrap`, [`ViaAnyhow`] for `&ErrorWrap`,
+/// and [`ViaStdError`] for the wrapper itself. This way errors that are standard errors as well (like `mlua::Error`
+/// with its `send` feature) are still converted directly.
+pub struct ErrorWrap<E, T> {
+    err: Cell<Option<E>>,
+    _target: PhantomData<fn() -> T>,
+}
+
+impl<E, T> ErrorWrap<E, T> {
+    pub fn new(err: E) -> Self {
+        Self {
+            err: Cell::new(Some(err)),
+            _target: PhantomData,
+        }
+    }
+
+    fn take(&self) -> E {
+        self.err
+            .take()
+            .expect("A binding error can only be converted once")
+    }
+}
+
+/// Conversion for errors that can be converted into the Lua error directly (this includes `mlua::Error` itself)
+pub trait ViaInto<T> {
+    fn to_lua_error(&self, external: fn(ExternalError) -> T) -> T;
+}
+
+impl<E: Into<T>, T> ViaInto<T> for &&ErrorWrap<E, T> {
+    fn to_lua_error(&self, _: fn(ExternalError) -> T) -> T {
+        self.take().into()
+    }
+}
+
+/// Conversion for standard errors, which are wrapped as external Lua errors
+pub trait ViaStdError<T> {
+    fn to_lua_error(&self, external: fn(ExternalError) -> T) -> T;
+}
+
+impl<E: StdError + Send + Sync + 'static, T> ViaStdError<T> for ErrorWrap<E, T> {
+    fn to_lua_error(&self, external: fn(ExternalError) -> T) -> T {
+        external(Arc::new(self.take()))
+    }
+}
+
+/// Conversion for `anyhow::Error`, which isn't a standard error itself, but can be turned into one. The trait is
+/// always there for the generated code to import, but it's only implemented with the `anyhow` feature
+pub trait ViaAnyhow<T> {
+    fn to_lua_error(&self, external: fn(ExternalError) -> T) -> T;
+}
+
+#[cfg(feature = "anyhow")]
+impl<T> ViaAnyhow<T> for &ErrorWrap<anyhow::Error, T> {
+    fn to_lua_error(&self, external: fn(ExternalError) -> T) -> T {
+        let err: Box<dyn StdError + Send + Sync> = self.take().into();
+        external(err.into())
+    }
+}
+
+/// Fields that a `#[mlua_bindgen]` struct adds to its userdata. `F` is always `mlua::UserDataFields<T>`.
+pub trait StructFields<F> {
+    fn add_struct_fields(fields: &mut F);
+}
+
+/// A marker used to add the struct fields of `T` to its userdata, if it has any.
+///
+/// Impl blocks don't know whether their type is a `#[mlua_bindgen]` struct, so we use autoref-based specialization
+/// again: [`ViaStructFields`] is implemented for the marker itself when `T` has struct fields, while [`NoStructFields`]
+/// is implemented for its reference and does nothing.
+pub struct StructFieldsOf<T>(PhantomData<fn() -> T>);
+
+impl<T> StructFieldsOf<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for StructFieldsOf<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds the fields of a `#[mlua_bindgen]` struct
+pub trait ViaStructFields<F> {
+    fn add_struct_fields(&self, fields: &mut F);
+}
+
+impl<T: StructFields<F>, F> ViaStructFields<F> for StructFieldsOf<T> {
+    fn add_struct_fields(&self, fields: &mut F) {
+        T::add_struct_fields(fields)
+    }
+}
+
+/// The fallback for types without struct fields
+pub trait NoStructFields<F> {
+    fn add_struct_fields(&self, _fields: &mut F) {}
+}
+
+impl<T, F> NoStructFields<F> for &StructFieldsOf<T> {}
+
+/// Registrations that a part of a userdata type (`#[mlua_bindgen(partial = name)]`) adds to its main impl block.
+/// `ID` is a hash of the part name, and `R` is one of [`FieldsPart`], [`MethodsPart`], [`FuncsPart`],
+/// [`ClassFieldsPart`] or [`ClassMethodsPart`].
+pub trait UserDataPart<R: PartRegistrar, const ID: u64> {
+    fn register_part(registrar: R) -> R::Output;
+}
+
+/// What a part registers its items to
+pub trait PartRegistrar {
+    type Output;
+
+    /// The output of parts that don't exist
+    fn skip(self) -> Self::Output;
+}
+
+/// The userdata fields (`mlua::UserDataFields<T>`) of a part
+pub struct FieldsPart<'a, F>(pub &'a mut F);
+
+impl<F> PartRegistrar for FieldsPart<'_, F> {
+    type Output = ();
+
+    fn skip(self) {}
+}
+
+/// The userdata methods (`mlua::UserDataMethods<T>`) of a part
+pub struct MethodsPart<'a, M>(pub &'a mut M);
+
+impl<M> PartRegistrar for MethodsPart<'_, M> {
+    type Output = ();
+
+    fn skip(self) {}
+}
+
+/// The Lua state and the type table (see `as_table`) that part functions are set to
+pub struct FuncsPart<'a, L, T, E> {
+    pub lua: &'a L,
+    pub table: &'a T,
+    _error: PhantomData<fn() -> E>,
+}
+
+impl<'a, L, T, E> FuncsPart<'a, L, T, E> {
+    pub fn new(lua: &'a L, table: &'a T) -> Self {
+        Self {
+            lua,
+            table,
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<L, T, E> PartRegistrar for FuncsPart<'_, L, T, E> {
+    type Output = Result<(), E>;
+
+    fn skip(self) -> Self::Output {
+        Ok(())
+    }
+}
+
+/// The userdata fields of a part that its constants are added to, when the type is exported as a proxy (so the
+/// class items live on the userdata type itself)
+pub struct ClassFieldsPart<'a, F>(pub &'a mut F);
+
+impl<F> PartRegistrar for ClassFieldsPart<'_, F> {
+    type Output = ();
+
+    fn skip(self) {}
+}
+
+/// The userdata methods of a part that its functions are added to, when the type is exported as a proxy
+pub struct ClassMethodsPart<'a, M>(pub &'a mut M);
+
+impl<M> PartRegistrar for ClassMethodsPart<'_, M> {
+    type Output = ();
+
+    fn skip(self) {}
+}
+
+/// A marker used to register the part `ID` of `T`, if it exists.
+///
+/// Parts can be disabled (i.e. with `#[cfg(...)]`), so their main impl block can't rely on them. Like with
+/// [`StructFieldsOf`], [`ViaUserDataPart`] is implemented for the marker itself when the part exists, while
+/// [`NoUserDataPart`] is implemented for its reference and registers nothing.
+pub struct PartOf<T, const ID: u64>(PhantomData<fn() -> T>);
+
+impl<T, const ID: u64> PartOf<T, ID> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T, const ID: u64> Default for PartOf<T, ID> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Registers an existing part
+pub trait ViaUserDataPart<R: PartRegistrar> {
+    fn register_part(&self, registrar: R) -> R::Output;
+}
+
+impl<T: UserDataPart<R, ID>, R: PartRegistrar, const ID: u64> ViaUserDataPart<R> for PartOf<T, ID> {
+    fn register_part(&self, registrar: R) -> R::Output {
+        T::register_part(registrar)
+    }
+}
+
+/// The fallback for parts that don't exist
+pub trait NoUserDataPart<R: PartRegistrar> {
+    fn register_part(&self, registrar: R) -> R::Output {
+        registrar.skip()
+    }
+}
+
+impl<T, R: PartRegistrar, const ID: u64> NoUserDataPart<R> for &PartOf<T, ID> {}
+
+/// Pin the output type of a future. Async blocks can't be annotated with their return type, which is
+/// a problem when they use `?`, so we do this instead.
+pub fn typed_future<T, F: std::future::Future<Output = T>>(fut: F) -> F {
+    fut
+}
+
+/// Get the function of a bad argument error, as it's named in Lua. With the `error-location` feature, it's followed by
+/// the Rust file and line of the binding, i.e. `Vector.new (at src/vector.rs:12)`.
+pub fn bad_argument_target(lua_name: &str, location: Option<(&str, u32)>) -> String {
+    match location {
+        Some((file, line)) => format!("{lua_name} (at {file}:{line})"),
+        None => lua_name.to_owned(),
+    }
+}
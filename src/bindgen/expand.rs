@@ -1,12 +1,21 @@
 //! Everything related to expanding (i.e. transforming rust structures into luau source code strings)
 
 use super::{
-    types::{LuaEnum, LuaFunc, LuaModule, LuaStruct},
+    types::{ItemDoc, LuaEnum, LuaFunc, LuaModule, LuaStruct},
     utils::add_tabs,
     USERDATA_CHAR
 };
 use std::fmt::Write;
 
+/// Write a doc comment (if present) line by line
+fn write_doc(to: &mut String, doc: &ItemDoc) {
+    if let Some(ref doc) = doc {
+        for line in doc.lines() {
+            writeln!(to, "--- {line}").unwrap();
+        }
+    }
+}
+
 /// I'm not sure thy it's a trait, but okay - maybe for consistency.
 pub trait LuaExpand {
     /// Lua expand will take a reference to self, and expand to 2 strings:
@@ -20,21 +29,20 @@ impl LuaExpand for LuaFunc {
         let mut expanded = String::new();
 
         let name = &self.name;
+        let generics = self.get_fmt_generics();
         let ret_ty = &self.return_ty.to_string();
         let args = self.get_fmt_args();
 
         // First we write the doc string to our function, if it is present
-        if let Some(ref doc) = self.doc {
-            writeln!(&mut expanded, "--- {doc}").unwrap();
-        }
+        write_doc(&mut expanded, &self.doc);
 
         // Depending on the nesting, luau function declarations aren't the same.
         // Global functions are declared directly as function {name}({named args}): {ret type},
         // but nested functions (included in types or )
         if inside_parent {
-            writeln!(&mut expanded, "{name}: function({args}){ret_ty}").unwrap();
+            writeln!(&mut expanded, "{name}: function{generics}({args}){ret_ty}").unwrap();
         } else {
-            writeln!(&mut expanded, "global function {name}({args}){ret_ty}\nend").unwrap();
+            writeln!(&mut expanded, "global function {name}{generics}({args}){ret_ty}\nend").unwrap();
         }
 
         (String::new(), expanded)
@@ -49,9 +57,7 @@ impl LuaExpand for LuaModule {
         let name = &self.name;
 
         // First we write the doc string to our function, if it is present
-        if let Some(ref doc) = self.doc {
-            writeln!(&mut expanded, "--- {doc}").unwrap();
-        }
+        write_doc(&mut expanded, &self.doc);
 
         // Depending on the nesting, luau function declarations aren't the same.
         // Global functions are declared directly as function {name}({named args}): {ret type},
@@ -104,9 +110,7 @@ impl LuaExpand for LuaEnum {
         let name = &self.name;
 
         // First we write the doc string to our function, if it is present
-        if let Some(ref doc) = self.doc {
-            writeln!(&mut expanded, "--- {doc}").unwrap();
-        }
+        write_doc(&mut expanded, &self.doc);
 
         // Depending on the nesting, luau function declarations aren't the same.
         // Global functions are declared directly as function {name}({named args}): {ret type},
@@ -134,40 +138,68 @@ impl LuaExpand for LuaStruct {
         let mut expanded = String::new();
         let mut global_ty = String::new();
 
-        let name = &self.name;
+        let (name, ty_name) = (&self.name, &self.ty_name);
 
         // First we expand the type
 
-        if let Some(ref doc) = self.doc {
-            writeln!(&mut global_ty, "--- {doc}").unwrap();
-        }
+        write_doc(&mut global_ty, &self.doc);
 
-        writeln!(&mut global_ty, "global type {USERDATA_CHAR}{name} = record").unwrap();
+        writeln!(
+            &mut global_ty,
+            "global type {USERDATA_CHAR}{ty_name} = record"
+        )
+        .unwrap();
 
         for field in self.fields.iter() {
             let fname = field.name.clone();
-            let fty = field.ty.clone();
+            let fty = &field.ty;
             writeln!(&mut global_ty, "    {fname}: {fty}").unwrap();
         }
 
         for method in self.methods.iter() {
+            write_doc(&mut global_ty, &method.doc);
             let fname = method.name.clone();
-            let fty = method.as_ty_impl(name, true);
+            let fty = method.as_ty_impl(true);
+            writeln!(&mut global_ty, "    {fname}: {fty}").unwrap();
+        }
+
+        for function in self.functions.iter() {
+            write_doc(&mut global_ty, &function.doc);
+            let fname = function.name.clone();
+            let fty = function.as_ty_impl(false);
             writeln!(&mut global_ty, "    {fname}: {fty}").unwrap();
         }
 
         for meta_func in self.meta_funcs.iter() {
+            write_doc(&mut global_ty, &meta_func.doc);
             let fname = meta_func.name.clone();
-            let fty = meta_func.as_ty_impl(name, false);
+            let fty = meta_func.as_meta_ty(ty_name, false);
+            writeln!(&mut global_ty, "    metamethod {fname}: {fty}").unwrap();
+        }
+
+        for meta_method in self.meta_methods.iter() {
+            write_doc(&mut global_ty, &meta_method.doc);
+            let fname = meta_method.name.clone();
+            let fty = meta_method.as_meta_ty(ty_name, true);
             writeln!(&mut global_ty, "    metamethod {fname}: {fty}").unwrap();
         }
 
+        // Proxies are userdata of the type itself, so the class items are declared in the type
+        if !self.as_table {
+            let class_ty = self.expand_class();
+            write!(&mut global_ty, "{class_ty}").unwrap();
+        }
+
         writeln!(&mut global_ty, "end").unwrap();
 
-        // Now we expand the table
+        // Now we expand the class
 
-        if let Some(ref doc) = self.doc {
-            writeln!(&mut expanded, "--- {doc}").unwrap();
+        write_doc(&mut expanded, &self.doc);
+
+        if !self.as_table {
+            let declare = if inside_parent { "" } else { "global " };
+            writeln!(&mut expanded, "{declare}{name}: {USERDATA_CHAR}{ty_name}").unwrap();
+            return (global_ty, expanded);
         }
 
         if inside_parent {
@@ -176,11 +208,8 @@ impl LuaExpand for LuaStruct {
             writeln!(&mut expanded, "global record {name}").unwrap();
         }
 
-        for func in self.funcs.iter() {
-            let fname = func.name.clone();
-            let fty = func.as_ty_impl(name, false);
-            writeln!(&mut expanded, "    {fname}: {fty}").unwrap();
-        }
+        let class_ty = self.expand_class();
+        write!(&mut expanded, "{class_ty}").unwrap();
 
         // let comma = if inside_parent { "," } else { "" };
 
@@ -191,3 +220,36 @@ impl LuaExpand for LuaStruct {
         (global_ty, expanded)
     }
 }
+
+impl LuaStruct {
+    /// Expand the class items (constants, functions and constructors) into record entries. Constants that are
+    /// fields already aren't declared twice
+    fn expand_class(&self) -> String {
+        let mut expanded = String::new();
+
+        for constant in self.constants.iter() {
+            let cname = constant.name.clone();
+            if !self.as_table && self.fields.iter().any(|field| field.name == cname) {
+                continue;
+            }
+            let cty = &constant.ty;
+            writeln!(&mut expanded, "    {cname}: {cty}").unwrap();
+        }
+
+        for func in self.funcs.iter() {
+            write_doc(&mut expanded, &func.doc);
+            let fname = func.name.clone();
+            let fty = func.as_ty_impl(false);
+            writeln!(&mut expanded, "    {fname}: {fty}").unwrap();
+        }
+
+        // Constructors make the class itself callable, so it receives the class as `self`
+        for constructor in self.constructors.iter() {
+            write_doc(&mut expanded, &constructor.doc);
+            let fty = constructor.as_ty_impl(true);
+            writeln!(&mut expanded, "    metamethod __call: {fty}").unwrap();
+        }
+
+        expanded
+    }
+}
@@ -9,7 +9,7 @@ use std::fs;
 use std::{collections::HashMap, path::PathBuf};
 use syn::Attribute;
 use syn::Item;
-use types::{LuaFile, LuaModule};
+use types::{resolve_bases, LuaFile, LuaModule};
 use utils::{find_attr, get_attribute_args};
 use walkdir::WalkDir;
 
@@ -70,7 +70,12 @@ impl ParsedFile {
             }
         }
 
-        let main_mod = main_mod.ok_or(Error::MainModules { many: false })?;
+        let mut main_mod = main_mod.ok_or(Error::MainModules { many: false })?;
+
+        // Extended types declare the members of their bases, which can be declared in any module
+        let mut modules: Vec<&mut LuaModule> = mod_map.values_mut().collect();
+        modules.push(&mut main_mod);
+        resolve_bases(&mut modules)?;
 
         // Now we need to insert modules appropriately, starting from the main module
         loop {
@@ -2,10 +2,10 @@
 
 use shared::{
     enums::ParsedEnum,
-    funcs::ParsedFunc,
-    impls::{FieldKind, ParsedImpl},
+    funcs::{is_option_ty, result_ok_ty, FuncKind, Instantiation, ParsedFunc},
+    impls::{FieldKind, ParsedField, ParsedImpl},
     mods::{ModuleItem, ModulePath, ParsedModule},
-    utils::{remove_lua_prefix, LastPathIdent},
+    utils::{remove_lua_prefix, LastPathIdent, NameRules},
     ToTokens,
 };
 use std::{
@@ -52,7 +52,8 @@ fn type_map<'a>() -> TypeMap<'a> {
         LuaType::Userdata => (AnyUserData, LightUserData, UserDataRef, UserDataRefMut),
         LuaType::Function => (Function),
         LuaType::Void => (()),
-        LuaType::Any => (Value)
+        LuaType::Any => (Value),
+        LuaType::Variadic(Box::new(LuaType::Any)) => (MultiValue)
     }
 }
 
@@ -77,6 +78,12 @@ pub enum LuaType {
     Either((Box<LuaType>, Box<LuaType>)),
     /// Tuples can only be used as return types
     Tuple(Vec<LuaType>),
+    /// An inline record type with named fields (i.e. `{x: number, name: string?}`). Optional fields are
+    /// declared with [`LuaType::Optional`]
+    Record(Vec<(String, LuaType)>),
+    /// A variable amount of values (i.e. `mlua::Variadic<T>` or `MultiValue`). Can only be used as the last
+    /// argument (`...: T`) or the last return value (`...T`)
+    Variadic(Box<LuaType>),
     Error,
     Table,
     Thread,
@@ -88,6 +95,10 @@ pub enum LuaType {
     /// These are passed directly as string, as they're simply a reference to a
     /// defined type (i.e. through [`mlua_bindgen`] macro)
     Custom(String),
+    /// An open type parameter of a generic function (i.e. `T` in `function<T>(val: T): T`)
+    Generic(String),
+    /// A type declared by the user (with `#[lua_type("...")]` or `#[lua_return("...")]`), which is written as it is
+    Verbatim(String),
     /// Any type in lua. Only works if you use [`Value`] in your arguments
     Any,
 }
@@ -122,6 +133,9 @@ impl LuaType {
                     }
 
                     Self::Optional(Box::new(inner_ty))
+                } else if ident == "Variadic" {
+                    let inner_ty = Self::from_syn_ty(parse_inner_ty(ty_path)?)?;
+                    Self::Variadic(Box::new(inner_ty))
                 } else if ident == "Vec" {
                     let inner_ty = Self::from_syn_ty(parse_inner_ty(ty_path)?)?;
                     Self::Array(Box::new(inner_ty))
@@ -148,6 +162,9 @@ impl LuaType {
                     for ty in tup.elems.iter() {
                         tys.push(LuaType::from_syn_ty(ty)?);
                     }
+                    if tys.iter().rev().skip(1).any(LuaType::is_variadic) {
+                        return Err(Error::ParseErr { message: "Variadic values can only be placed at the end of a tuple".to_owned() });
+                    }
                     Self::Tuple(tys)
                     // return Err(Error::Unimplemented { message: "Multi-value tuples aren't supported currently".to_owned() });
                 } else {
@@ -170,6 +187,49 @@ impl LuaType {
             _ => false,
         }
     }
+
+    /// Check whether this type is variadic
+    pub fn is_variadic(&self) -> bool {
+        matches!(self, Self::Variadic(_))
+    }
+
+    /// Type parameters look exactly like custom types in Rust, so this will turn the custom types that are named
+    /// like the provided type parameters into [`LuaType::Generic`]
+    pub fn with_generics(self, generics: &[String]) -> Self {
+        self.map_custom(&|name| match generics.contains(&name) {
+            true => Self::Generic(name),
+            false => Self::Custom(name),
+        })
+    }
+
+    /// `Self` isn't recognized in luau, so this will turn the custom `Self` types into the userdata type of
+    /// the owning type (`ty_name`)
+    pub fn with_self(self, ty_name: &str) -> Self {
+        self.map_custom(&|name| match name == "Self" {
+            true => Self::Custom(ty_name.to_owned()),
+            false => Self::Custom(name),
+        })
+    }
+
+    /// Map every custom type inside of this type (including the nested ones) with `map`
+    fn map_custom(self, map: &impl Fn(String) -> Self) -> Self {
+        let map_box = |ty: Box<LuaType>| Box::new(ty.map_custom(map));
+        match self {
+            Self::Custom(name) => map(name),
+            Self::Optional(ty) => Self::Optional(map_box(ty)),
+            Self::Array(ty) => Self::Array(map_box(ty)),
+            Self::Variadic(ty) => Self::Variadic(map_box(ty)),
+            Self::Either((left, right)) => Self::Either((map_box(left), map_box(right))),
+            Self::Tuple(tys) => Self::Tuple(tys.into_iter().map(|ty| ty.map_custom(map)).collect()),
+            Self::Record(fields) => Self::Record(
+                fields
+                    .into_iter()
+                    .map(|(name, ty)| (name, ty.map_custom(map)))
+                    .collect(),
+            ),
+            ty => ty,
+        }
+    }
 }
 
 impl std::fmt::Display for LuaType {
@@ -197,6 +257,8 @@ impl std::fmt::Display for LuaType {
                 LuaType::Void => "".to_owned(),
                 LuaType::Either((left, right)) => format!("{left} | {right}"),
                 LuaType::Custom(ty) => format!("{USERDATA_CHAR}{}", ty.clone()),
+                LuaType::Generic(name) => name.clone(),
+                LuaType::Verbatim(ty) => ty.clone(),
                 LuaType::Tuple(tys) => {
                     if tys.len() == 1 {
                         format!("({})", tys[0])
@@ -214,6 +276,19 @@ impl std::fmt::Display for LuaType {
                     }
                 }
                 LuaType::Any => "any".to_owned(),
+                // Variadic types are type packs, which are declared differently in arguments (`...: T`),
+                // so this is only their return form
+                LuaType::Variadic(ty) => format!("...{ty}"),
+                LuaType::Record(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|(name, ty)| {
+                            let optional = if ty.is_optional() { "?" } else { "" };
+                            format!("{name}{optional}: {ty}")
+                        })
+                        .collect();
+                    format!("{{{}}}", fields.join(", "))
+                }
             }
         )
     }
@@ -255,17 +330,26 @@ fn parse_inner_tys(input: &syn::TypePath) -> Result<Vec<&Type>, Error> {
 pub type ItemDoc = Option<String>;
 
 /// An argument for the luau function
+#[derive(Clone)]
 pub struct LuaArg {
     pub name: String,
     pub ty: LuaType,
-    /// Optional args can be ignored when calling a function. In rust it's declared as [`Option<T>`],
-    /// while in Lua it's just `T?`
+    /// Optional args can be ignored when calling a function. In rust it's declared as [`Option<T>`]
+    /// (or has a default value), while in Lua it's just `T?`
     pub optional: bool,
+    /// The default value expression, if the argument has one
+    pub default: Option<String>,
+    /// Descriptions of the argument's checks (i.e. "must be in range 0..=100")
+    pub checks: Vec<String>,
 }
 
 impl std::fmt::Display for LuaArg {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let optional = if self.ty.is_optional() { "?" } else { "" };
+        if let LuaType::Variadic(ty) = &self.ty {
+            return write!(f, "...: {ty}");
+        }
+
+        let optional = if self.optional { "?" } else { "" };
         let name = &self.name;
         let ty = &self.ty;
         write!(f, "{name}{optional}: {ty}")
@@ -275,6 +359,7 @@ impl std::fmt::Display for LuaArg {
 /// A return type for a luau function
 ///
 /// This only exists to simplify working with functions that return Option<T>
+#[derive(Clone)]
 pub struct LuaReturn {
     pub ty: LuaType,
     pub optional: bool,
@@ -293,43 +378,90 @@ impl std::fmt::Display for LuaReturn {
 }
 
 /// A field for luau structs
+#[derive(Clone)]
 pub struct LuaField {
     pub name: String,
     pub ty: LuaType,
 }
 
+impl LuaField {
+    /// Convert a parsed field into a Lua field. Only getters (and static fields) are declared, since a setter alone
+    /// doesn't make a field readable. Meta fields aren't accessible from instances, so they aren't declared either
+    pub fn from_parsed(parsed: ParsedField, rules: NameRules) -> Result<Option<Self>, Error> {
+        if let FieldKind::Setter | FieldKind::FunctionSetter | FieldKind::Meta = parsed.kind {
+            return Ok(None);
+        }
+
+        let ret_ty = &parsed.func.return_ty;
+        let ty = match &parsed.func.lua_return {
+            Some(lua_type) => LuaType::Verbatim(lua_type.clone()),
+            None => LuaType::from_syn_ty(result_ok_ty(ret_ty).unwrap_or(ret_ty))?,
+        };
+
+        Ok(Some(Self {
+            name: parsed.func.lua_name(rules),
+            ty,
+        }))
+    }
+}
+
 /// A field for luau enums
 pub struct LuaVariant {
     pub name: String,
 }
 
 /// A luau function that contains its name, doc, return type, named [`LuaArg`] and its parent module name
+#[derive(Clone)]
 pub struct LuaFunc {
     pub name: String,
     pub doc: ItemDoc,
+    /// Type parameters of a generic function, that were left open (i.e. `<T>`)
+    pub generics: Vec<String>,
     pub return_ty: LuaReturn,
     pub args: Vec<LuaArg>,
 }
 
 impl LuaFunc {
-    pub fn from_parsed(parsed: ParsedFunc) -> Result<Self, Error> {
-        let name = parsed.name.to_string();
-        let name = remove_lua_prefix(name);
+    /// Convert a parsed function into all of its Lua functions. Instantiated generic functions are declared
+    /// once per instance (with concrete types), while the rest are declared as they are.
+    pub fn from_parsed_instances(parsed: ParsedFunc, rules: NameRules) -> Result<Vec<Self>, Error> {
+        if parsed.instantiations.is_empty() {
+            return Ok(vec![Self::from_parsed(parsed, rules)?]);
+        }
+
+        parsed
+            .instantiations
+            .iter()
+            .map(|instance| Self::from_parsed(parsed.instantiate(instance), rules))
+            .collect()
+    }
+
+    /// Convert a parsed function into a Lua function, named by the naming `rules` of its parent
+    /// (the same ones the macros register it with)
+    pub fn from_parsed(parsed: ParsedFunc, rules: NameRules) -> Result<Self, Error> {
+        let name = parsed.lua_name(rules);
+        let generics: Vec<String> = parsed
+            .generics
+            .type_params()
+            .map(|param| param.ident.to_string())
+            .collect();
 
         let return_ty = {
-            let ty = LuaType::from_syn_ty(&parsed.return_ty)?;
+            // Result errors are raised in Lua, so only the successful type is returned
+            let ret_ty = result_ok_ty(&parsed.return_ty).unwrap_or(&parsed.return_ty);
+            let ty = match &parsed.lua_return {
+                Some(lua_return) => LuaType::Verbatim(lua_return.clone()),
+                None => LuaType::from_syn_ty(ret_ty)?.with_generics(&generics),
+            };
             let optional = ty.is_optional();
 
             LuaReturn { ty, optional }
         };
 
-        // Get the amount of required arguments by mlua. These have no use in luau declaration
-        let skip_args = parsed.req_arg_count();
-
         let mut args = Vec::new();
-        for (ind, arg) in parsed.args.iter().enumerate() {
-            // If the index is smaller than the amount of required arguments - skip
-            if ind < skip_args {
+        for arg in parsed.args.iter() {
+            // Required arguments by mlua (whether declared by the user or not) have no use in luau declaration
+            if arg.required {
                 continue;
             }
 
@@ -338,23 +470,95 @@ impl LuaFunc {
                 _ => continue,
             };
 
-            let arg_ty = LuaType::from_syn_ty(&arg.ty)?;
-            let optional = arg_ty.is_optional();
+            // Declared types are used as they are, though they're still optional if the Rust type is
+            let (arg_ty, optional) = match &arg.lua_type {
+                Some(lua_type) => (LuaType::Verbatim(lua_type.clone()), is_option_ty(&arg.ty)),
+                None => {
+                    let ty = LuaType::from_syn_ty(&arg.ty)?.with_generics(&generics);
+                    let optional = ty.is_optional();
+                    (ty, optional)
+                }
+            };
+            if args.last().is_some_and(|arg: &LuaArg| arg.ty.is_variadic()) {
+                return Err(Error::ParseErr { message: format!("Function `{name}` can only have a variadic argument at the end") });
+            }
+            let default = arg.default.as_ref().map(|expr| expr.to_token_stream().to_string());
+            let optional = optional || default.is_some();
+            let checks = arg.checks.iter().map(|check| check.describe()).collect();
             args.push(LuaArg {
                 name: arg_name,
                 ty: arg_ty,
                 optional,
+                default,
+                checks,
             });
         }
 
+        // Default values and checks can't be declared in Lua types, so we mention them in the doc instead
+        let doc_lines: Vec<String> = args
+            .iter()
+            .flat_map(|arg| {
+                let default = arg
+                    .default
+                    .iter()
+                    .map(|default| format!("`{}` defaults to `{default}`", arg.name));
+                let checks = arg
+                    .checks
+                    .iter()
+                    .map(|check| format!("`{}` {check}", arg.name));
+                default.chain(checks)
+            })
+            .collect();
+        let doc = (!doc_lines.is_empty()).then(|| doc_lines.join("\n"));
+
+        // Table argument functions take a single table, which is declared as an inline record
+        if parsed.table_args {
+            let fields = args
+                .into_iter()
+                .map(|arg| {
+                    let ty = match arg.optional && !arg.ty.is_optional() {
+                        true => LuaType::Optional(Box::new(arg.ty)),
+                        false => arg.ty,
+                    };
+                    (arg.name, ty)
+                })
+                .collect();
+            args = vec![LuaArg {
+                name: "args".to_owned(),
+                ty: LuaType::Record(fields),
+                optional: false,
+                default: None,
+                checks: Vec::new(),
+            }];
+        }
+
         Ok(Self {
             name,
-            doc: None,
+            doc,
+            generics,
             return_ty,
             args,
         })
     }
 
+    /// Resolve the `Self` types of the arguments and the return type to the userdata type of the owning type
+    /// (see [`LuaType::with_self`])
+    pub fn with_self(mut self, ty_name: &str) -> Self {
+        self.return_ty.ty = self.return_ty.ty.with_self(ty_name);
+        for arg in self.args.iter_mut() {
+            arg.ty = arg.ty.clone().with_self(ty_name);
+        }
+        self
+    }
+
+    /// Get luau formatted generics of the function (i.e. `<T, U>`), or an empty string if it has none
+    pub fn get_fmt_generics(&self) -> String {
+        match self.generics.is_empty() {
+            true => String::new(),
+            false => format!("<{}>", self.generics.join(", ")),
+        }
+    }
+
     /// Get luau formatted arguments. Instead of getting an entire function declaration, it only returns
     /// a formatted argument list like so: `number, string?, number`. (Yes, without  parantheses)
     pub fn get_fmt_args(&self) -> String {
@@ -374,26 +578,21 @@ impl LuaFunc {
     /// Since `function` can't be used for returns, a more precise declaration is required:
     /// `(arg1, arg2, ...) -> return_type`
     pub fn as_ty(&self) -> String {
+        let generics = self.get_fmt_generics();
         let args = self.get_fmt_args();
         let return_ty = &self.return_ty;
-        format!("function({args}){return_ty}")
+        format!("function{generics}({args}){return_ty}")
     }
 
     /// The same as [`LuaFunc::as_ty`], but for impl functions (class functions or methods)
     ///
-    /// It takes a type name as an argument, and will replace all `Self` keywords with the name
-    /// of the type.
-    pub fn as_ty_impl(&self, ty: &String, is_method: bool) -> String {
+    /// `Self` isn't recognized in luau, so the function should have its `Self` types resolved already
+    /// (see [`LuaFunc::with_self`])
+    pub fn as_ty_impl(&self, is_method: bool) -> String {
         let args = self.get_fmt_args();
-
-        // We use this ugly and slow method for now, I'll change it in the future.
-        // We have to replace all Self arguments with the name type, since Self is not recognized
-        // in luau as a reference to a self type.
-        let args = args.replace("Self", ty);
-        let return_ty = self.return_ty.to_string().replace("Self", ty);
+        let return_ty = &self.return_ty;
 
         let self_arg = if is_method {
-            // format!("self: {ty}")
             "self".to_owned()
         } else {
             "".to_owned()
@@ -409,67 +608,199 @@ impl LuaFunc {
         // TODO: Sometimes arguments can be empty, so a trailing comma can cause issues in the future.
         format!("function({self_arg}{self_comma}{args}){return_ty}")
     }
+
+    /// The same as [`LuaFunc::as_ty_impl`], but for metamethods. Lua calls them with their operands in order, so
+    /// the userdata operand of meta methods is declared with its type (i.e. `function(self: uVec, other: uVec): uVec`)
+    pub fn as_meta_ty(&self, ty: &String, is_method: bool) -> String {
+        let mut operands = Vec::new();
+        if is_method {
+            operands.push(format!("self: {USERDATA_CHAR}{ty}"));
+        }
+        operands.extend(self.args.iter().map(|arg| arg.to_string()));
+
+        let operands = operands.join(", ");
+        let return_ty = &self.return_ty;
+        format!("function({operands}){return_ty}")
+    }
 }
 
 /// In luau described as both type and table
+#[derive(Clone)]
 pub struct LuaStruct {
     pub name: String,
+    /// The name of the userdata type (`u{ty_name}`). Unlike the table name, it always comes from the Rust type
+    /// (without its Lua prefix), since it's referenced by other declarations
+    pub ty_name: String,
     pub doc: ItemDoc,
     pub fields: Vec<LuaField>,
     pub funcs: Vec<LuaFunc>,
     pub methods: Vec<LuaFunc>,
+    /// Functions of the userdata itself (`#[function]`), which receive it as a regular argument instead of `self`
+    pub functions: Vec<LuaFunc>,
     pub meta_funcs: Vec<LuaFunc>,
+    /// Meta functions that receive the userdata itself (i.e. `fn __len(&self)`)
+    pub meta_methods: Vec<LuaFunc>,
+    /// Signatures of the class table's `__call` (`#[constructor]`). Constructors with the table form have two
+    pub constructors: Vec<LuaFunc>,
+    /// Constants of the class table (`#[constant]`)
+    pub constants: Vec<LuaField>,
+    /// The userdata type name of the base type (`extends`), whose members are declared in this type as well
+    /// (see [`resolve_bases`])
+    pub base: Option<String>,
+    /// Whether the class is a table (`as_table`). Otherwise it's a userdata proxy of the type, so its functions,
+    /// constants and constructors are declared in the userdata type
+    pub as_table: bool,
+}
+
+/// Get the name of a userdata type, as it's referenced by other declarations (see [`LuaStruct::ty_name`])
+fn userdata_ty_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(ty_path) => remove_lua_prefix(ty_path.path.last_ident().to_string()),
+        ty => ty.to_token_stream().to_string(),
+    }
 }
 
 impl LuaStruct {
-    pub fn from_parsed(parsed: ParsedImpl) -> Result<Self, Error> {
-        let name = parsed.name.to_token_stream().to_string();
-        // Remove the lua prefix of course, if it's present
-        let name = remove_lua_prefix(name);
+    pub fn from_parsed(parsed: ParsedImpl, rules: NameRules) -> Result<Self, Error> {
+        let name = parsed.lua_name(rules);
+        // Other declarations reference the type by its Rust name (see [`LuaType::from_syn_ident`]), which is the
+        // foreign type for remote impl blocks
+        let ty_name = userdata_ty_name(parsed.remote.as_deref().unwrap_or(&parsed.name));
+        let base = parsed.base.as_ref().map(|base| userdata_ty_name(&base.ty));
+        let member_rules = parsed.member_rules();
 
         let mut funcs = Vec::new();
         let mut fields = Vec::new();
         let mut methods = Vec::new();
+        let mut functions = Vec::new();
         let mut meta_funcs = Vec::new();
+        let mut meta_methods = Vec::new();
+        let mut constructors = Vec::new();
+        let mut constants = Vec::new();
 
         for func in parsed.funcs {
-            let lfunc = LuaFunc::from_parsed(func.func)?;
+            let lfunc = LuaFunc::from_parsed(func.func, member_rules)?.with_self(&ty_name);
             funcs.push(lfunc);
         }
 
         for method in parsed.methods {
-            let lmethod = LuaFunc::from_parsed(method.func)?;
-            methods.push(lmethod);
+            let lmethod = LuaFunc::from_parsed(method.func, member_rules)?.with_self(&ty_name);
+            match method.kind {
+                FuncKind::Function | FuncKind::FunctionMut => functions.push(lmethod),
+                _ => methods.push(lmethod),
+            }
         }
 
-        // We're pushing meta functions to the same vector, since they're by type the same as methods,
-        // the sole difference being their arguments.
+        // Meta functions are by type the same as methods, the sole difference being their arguments.
+        // The ones that receive the userdata itself are declared with `self`, like methods.
         for meta_func in parsed.meta_funcs {
-            let lmeta_func = LuaFunc::from_parsed(meta_func.func)?;
-            meta_funcs.push(lmeta_func);
+            // Meta functions keep their names, since Lua looks them up by their exact names
+            let lmeta_func =
+                LuaFunc::from_parsed(meta_func.func, NameRules::MEMBER)?.with_self(&ty_name);
+            match meta_func.kind {
+                FuncKind::MetaMethod | FuncKind::MetaMethodMut => meta_methods.push(lmeta_func),
+                _ => meta_funcs.push(lmeta_func),
+            }
         }
 
         for field in parsed.fields {
-            if let FieldKind::Getter = field.kind {
-                let fname = field.func.name.to_string();
-                let fty = LuaType::from_syn_ty(&field.func.return_ty)?;
+            fields.extend(
+                LuaField::from_parsed(field, member_rules)?.map(|field| LuaField {
+                    ty: field.ty.with_self(&ty_name),
+                    ..field
+                }),
+            );
+        }
 
-                fields.push(LuaField {
-                    name: fname,
-                    ty: fty,
-                });
+        // Constants marked with `field` are readable from instances as well
+        for constant in parsed.constants {
+            let field = LuaField {
+                name: constant.lua_name(),
+                ty: LuaType::from_syn_ty(&constant.ty)?.with_self(&ty_name),
+            };
+            if constant.field {
+                fields.push(field.clone());
+            }
+            constants.push(field);
+        }
+
+        if let Some(constructor) = parsed.constructor {
+            if constructor.table_form {
+                let mut table_func = constructor.func.clone();
+                table_func.table_args = true;
+                constructors.push(
+                    LuaFunc::from_parsed(constructor.func, member_rules)?.with_self(&ty_name),
+                );
+                constructors
+                    .push(LuaFunc::from_parsed(table_func, member_rules)?.with_self(&ty_name));
+            } else {
+                constructors.push(
+                    LuaFunc::from_parsed(constructor.func, member_rules)?.with_self(&ty_name),
+                );
             }
         }
 
         Ok(Self {
             name,
+            ty_name,
             doc: None,
             funcs,
             fields,
             methods,
+            functions,
             meta_funcs,
+            meta_methods,
+            constructors,
+            constants,
+            base,
+            as_table: parsed.as_table,
         })
     }
+
+    /// Declare the fields, methods and functions of a base type in this type, unless it declares them itself (since
+    /// only the missing members are looked up on the base). Metamethods aren't inherited, since Lua doesn't look
+    /// them up through `__index`.
+    pub fn inherit(&mut self, base: &LuaStruct) {
+        let declared: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .chain(self.methods.iter().map(|method| method.name.clone()))
+            .chain(self.functions.iter().map(|function| function.name.clone()))
+            .collect();
+        let inherited = |name: &String| !declared.contains(name);
+
+        self.fields.extend(
+            base.fields
+                .iter()
+                .filter(|field| inherited(&field.name))
+                .cloned(),
+        );
+        self.methods.extend(
+            base.methods
+                .iter()
+                .filter(|method| inherited(&method.name))
+                .cloned(),
+        );
+        self.functions.extend(
+            base.functions
+                .iter()
+                .filter(|function| inherited(&function.name))
+                .cloned(),
+        );
+    }
+
+    /// Merge another impl block of the same type (i.e. its part) into this one
+    pub fn merge(&mut self, other: LuaStruct) {
+        self.fields.extend(other.fields);
+        self.funcs.extend(other.funcs);
+        self.methods.extend(other.methods);
+        self.functions.extend(other.functions);
+        self.meta_funcs.extend(other.meta_funcs);
+        self.meta_methods.extend(other.meta_methods);
+        self.constructors.extend(other.constructors);
+        self.constants.extend(other.constants);
+    }
 }
 
 pub struct LuaEnum {
@@ -479,9 +810,8 @@ pub struct LuaEnum {
 }
 
 impl LuaEnum {
-    pub fn from_parsed(parsed: ParsedEnum) -> Result<Self, Error> {
-        let name = parsed.ident.to_string();
-        let name = remove_lua_prefix(name);
+    pub fn from_parsed(parsed: ParsedEnum, rules: NameRules) -> Result<Self, Error> {
+        let name = parsed.lua_name(rules);
 
         let variants = parsed
             .variants
@@ -518,21 +848,85 @@ impl LuaModule {
         let name = parsed.ident.to_string();
 
         let ismain = parsed.ismain;
+        let (func_rules, type_rules) = (parsed.func_rules(), parsed.type_rules());
         let mut funcs = Vec::new();
-        let mut impls = Vec::new();
+        let mut impls: Vec<LuaStruct> = Vec::new();
         let mut enums = Vec::new();
+        let mut parsed_impls = Vec::new();
+        let mut structs = Vec::new();
 
         for item in parsed.items {
             match item {
                 ModuleItem::Fn(func) => {
-                    funcs.push(LuaFunc::from_parsed(func)?);
+                    funcs.extend(LuaFunc::from_parsed_instances(func, func_rules)?);
                 }
                 ModuleItem::Enum(enm) => {
-                    enums.push(LuaEnum::from_parsed(enm)?);
+                    enums.push(LuaEnum::from_parsed(enm, type_rules)?);
                 }
-                ModuleItem::Impl(imp) => {
-                    impls.push(LuaStruct::from_parsed(imp)?);
+                ModuleItem::Impl(imp) => parsed_impls.push(imp),
+                ModuleItem::Struct(strct) => structs.push(strct),
+            }
+        }
+
+        // Parts of a type are declared together with its main impl block, and generic impls are declared once per
+        // instance (with their parts instantiated the same way)
+        let (parts, mains): (Vec<ParsedImpl>, Vec<ParsedImpl>) = parsed_impls
+            .into_iter()
+            .partition(|imp| imp.partial.is_some());
+        let mut merged_parts = vec![false; parts.len()];
+        for main in mains {
+            let type_parts: Vec<&ParsedImpl> = parts
+                .iter()
+                .enumerate()
+                .filter(|(_, part)| part.ty_ident() == main.ty_ident())
+                .map(|(ind, part)| {
+                    merged_parts[ind] = true;
+                    part
+                })
+                .collect();
+            let instances: Vec<Option<&Instantiation>> = match main.instantiations.is_empty() {
+                true => vec![None],
+                false => main.instantiations.iter().map(Some).collect(),
+            };
+
+            for instance in instances {
+                let instantiate = |imp: &ParsedImpl| match instance {
+                    Some(instance) => imp.instantiate(instance),
+                    None => imp.clone(),
+                };
+                let mut lua_struct = LuaStruct::from_parsed(instantiate(&main), type_rules)?;
+                for part in type_parts.iter() {
+                    lua_struct.merge(LuaStruct::from_parsed(instantiate(part), type_rules)?);
                 }
+                impls.push(lua_struct);
+            }
+        }
+        // Parts without a main impl block (i.e. in another module) are still declared together
+        for (part, merged) in parts.into_iter().zip(merged_parts) {
+            if merged {
+                continue;
+            }
+            let lua_struct = LuaStruct::from_parsed(part, type_rules)?;
+            match impls
+                .iter_mut()
+                .find(|imp| imp.ty_name == lua_struct.ty_name)
+            {
+                Some(existing) => existing.merge(lua_struct),
+                None => impls.push(lua_struct),
+            }
+        }
+
+        // Struct fields are registered by the impl of the same type, so they're declared in its userdata type.
+        // Structs without a `#[mlua_bindgen]` impl aren't userdata at all, so there's nothing to declare.
+        for strct in structs {
+            let ty_name = remove_lua_prefix(strct.ident.to_string());
+            let field_rules = strct.field_rules();
+            if let Some(imp) = impls.iter_mut().find(|imp| imp.ty_name == ty_name) {
+                let mut fields = Vec::new();
+                for field in strct.fields {
+                    fields.extend(LuaField::from_parsed(field, field_rules)?);
+                }
+                imp.fields.splice(0..0, fields);
             }
         }
 
@@ -563,6 +957,42 @@ impl LuaModule {
     }
 }
 
+/// Declare the members of base types in the types that extend them (see [`LuaStruct::inherit`]). Bases are looked
+/// up by their userdata type name across all modules, since they can be declared anywhere. Types that extend other
+/// extended types inherit the whole chain, with the closest base taking precedence.
+pub fn resolve_bases(modules: &mut [&mut LuaModule]) -> Result<(), Error> {
+    let declared: HashMap<String, LuaStruct> = modules
+        .iter()
+        .flat_map(|module| module.impls.iter())
+        .map(|lua_struct| (lua_struct.ty_name.clone(), lua_struct.clone()))
+        .collect();
+
+    for lua_struct in modules
+        .iter_mut()
+        .flat_map(|module| module.impls.iter_mut())
+    {
+        let mut chain = vec![lua_struct.ty_name.clone()];
+        let mut base_name = lua_struct.base.clone();
+        while let Some(name) = base_name {
+            if chain.contains(&name) {
+                return Err(Error::ParseErr {
+                    message: format!("`{}` extends itself through `{name}`", lua_struct.ty_name),
+                });
+            }
+            let base = declared.get(&name).ok_or_else(|| Error::ParseErr {
+                message: format!(
+                    "`{}` extends `{name}`, which isn't declared in any module",
+                    lua_struct.ty_name
+                ),
+            })?;
+            lua_struct.inherit(base);
+            chain.push(name);
+            base_name = base.base.clone();
+        }
+    }
+    Ok(())
+}
+
 /// Describes a lua file, which basically is similar to [`ParsedFile`], but contains
 /// useful information for Lua instead.
 pub struct LuaFile<'a> {
@@ -616,7 +1046,11 @@ impl<'a> LuaFile<'a> {
 
 #[cfg(test)]
 mod test {
-    use super::{Error, LuaType};
+    use super::{resolve_bases, Error, LuaExpand, LuaField, LuaFunc, LuaModule, LuaType};
+    use shared::funcs::{parse_func, FuncKind};
+    use shared::mods::parse_mod;
+    use shared::utils::{ItemAttributes, NameRules};
+    use syn::{parse_quote, ItemFn, ItemMod};
 
     #[test]
     fn lua_types() {
@@ -638,6 +1072,18 @@ mod test {
         let single_tuple = LuaType::Tuple(vec![LuaType::String]);
 
         assert_eq!(single_tuple.to_string(), "(string)".to_owned());
+
+        let variadic = LuaType::Variadic(Box::new(LuaType::Number));
+        assert_eq!(variadic.to_string(), "...number".to_owned());
+
+        let record = LuaType::Record(vec![
+            ("x".to_owned(), LuaType::Number),
+            ("name".to_owned(), LuaType::Optional(Box::new(LuaType::String))),
+        ]);
+        assert_eq!(record.to_string(), "{x: number, name?: string}".to_owned());
+
+        let generic = LuaType::Array(Box::new(LuaType::Generic("T".to_owned())));
+        assert_eq!(generic.to_string(), "{T}".to_owned());
     }
 
     #[test]
@@ -652,6 +1098,12 @@ mod test {
         let array = LuaType::from_syn_ty(&syn::parse_str("[String; 12]")?)?;
         assert!(matches!(array, LuaType::Array(inner) if matches!(*inner, LuaType::String)));
 
+        let variadic = LuaType::from_syn_ty(&syn::parse_str("mlua::Variadic<String>")?)?;
+        assert!(matches!(variadic, LuaType::Variadic(inner) if matches!(*inner, LuaType::String)));
+
+        let multi = LuaType::from_syn_ty(&syn::parse_str("MultiValue")?)?;
+        assert!(matches!(multi, LuaType::Variadic(inner) if matches!(*inner, LuaType::Any)));
+
         Ok(())
     }
 
@@ -663,6 +1115,678 @@ mod test {
             recursive.is_err(),
             "Recursive option types should fail to parse"
         );
+
+        let variadic = LuaType::from_syn_ty(&syn::parse_str("(Variadic<u32>, String)")?);
+        assert!(
+            variadic.is_err(),
+            "Variadic values in the middle of a tuple should fail to parse"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn declared_lua_types() -> Result<(), Error> {
+        let item: ItemFn = parse_quote! {
+            #[lua_return("{string}")]
+            fn filter(
+                #[lua_type("function(string): boolean")] pred: Function,
+                #[lua_type("{string}")] items: Option<Table>,
+            ) -> Table {}
+        };
+
+        // Declared types are used as they are, instead of the Rust types
+        let func = LuaFunc::from_parsed(parse_func(item, &FuncKind::Func)?, NameRules::ITEM)?;
+        assert_eq!(
+            func.as_ty(),
+            "function(pred: function(string): boolean, items?: {string}): {string}".to_owned()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn generic_funcs() -> Result<(), Error> {
+        let item: ItemFn = parse_quote! {
+            fn lua_largest<T: PartialOrd>(a: T, b: Option<T>) -> T {}
+        };
+
+        // Open type parameters are declared as they are
+        let open =
+            LuaFunc::from_parsed(parse_func(item.clone(), &FuncKind::Func)?, NameRules::ITEM)?;
+        assert_eq!(open.as_ty(), "function<T>(a: T, b?: T): T".to_owned());
+
+        // While each instance is declared separately, with its own types
+        let mut parsed = parse_func(item, &FuncKind::Func)?;
+        parsed.apply_attrs(syn::parse_str::<ItemAttributes>(
+            "instantiate(int_largest(T = i64), any_largest(T = mlua::Value))",
+        )?)?;
+        let instances = LuaFunc::from_parsed_instances(parsed, NameRules::ITEM)?;
+        let declared: Vec<(String, String)> = instances
+            .iter()
+            .map(|func| (func.name.clone(), func.as_ty()))
+            .collect();
+        assert_eq!(
+            declared,
+            vec![
+                (
+                    "int_largest".to_owned(),
+                    "function(a: number, b?: number): number".to_owned()
+                ),
+                (
+                    "any_largest".to_owned(),
+                    "function(a: any, b?: any): any".to_owned()
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn renamed_items() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod world {
+                #[mlua_bindgen]
+                fn get_position() {}
+
+                #[mlua_bindgen(name = "spawnAt")]
+                fn lua_spawn() {}
+
+                #[mlua_bindgen(preserve)]
+                fn lua_raw_query() {}
+
+                #[mlua_bindgen(rename_all = "camelCase")]
+                impl LuaEntity {
+                    #[get]
+                    fn max_health(this: &Self) -> u32 {}
+
+                    #[method]
+                    #[mlua_bindgen(name = "kill")]
+                    fn despawn(this: &Self) {}
+
+                    #[meta]
+                    fn __tostring(this: &Self) -> String {}
+                }
+
+                #[mlua_bindgen(name = "Kind")]
+                enum EntityKind {
+                    Player,
+                }
+            }
+        };
+        let attrs = syn::parse_str::<ItemAttributes>("rename_all = \"camelCase\"")?;
+        let module = LuaModule::from_parsed(parse_mod(attrs, item, true)?)?;
+
+        let funcs: Vec<&str> = module.funcs.iter().map(|func| func.name.as_str()).collect();
+        assert_eq!(funcs, vec!["getPosition", "spawnAt", "lua_raw_query"]);
+
+        // Types aren't affected by the module's `rename_all`, and are still referenced by their Rust names
+        let entity = &module.impls[0];
+        assert_eq!(
+            (entity.name.as_str(), entity.ty_name.as_str()),
+            ("Entity", "Entity")
+        );
+        assert_eq!(entity.fields[0].name, "maxHealth".to_owned());
+        assert_eq!(entity.methods[0].name, "kill".to_owned());
+        assert_eq!(entity.meta_funcs[0].name, "__tostring".to_owned());
+        assert_eq!(module.enums[0].name, "Kind".to_owned());
+        Ok(())
+    }
+
+    #[test]
+    fn struct_fields() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod game {
+                #[mlua_bindgen(rename_all = "camelCase")]
+                struct LuaPlayer {
+                    #[lua(get, set)]
+                    name: String,
+                    max_level: u32,
+                    #[lua(set)]
+                    health: f32,
+                    #[lua(skip)]
+                    secret: u64,
+                }
+
+                #[mlua_bindgen]
+                impl LuaPlayer {
+                    #[get]
+                    fn level(this: &Self) -> u32 {}
+                }
+
+                // Not userdata, so its fields aren't declared anywhere
+                #[mlua_bindgen]
+                struct Stats {
+                    kills: u32,
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        // Struct fields go first, and only readable fields are declared (fields without `#[lua]` aren't exposed)
+        assert_eq!(module.impls.len(), 1);
+        let fields: Vec<String> = module.impls[0]
+            .fields
+            .iter()
+            .map(|field| format!("{}: {}", field.name, field.ty))
+            .collect();
+        assert_eq!(fields, vec!["name: string", "level: number"]);
+        Ok(())
+    }
+
+    #[test]
+    fn partial_impls() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod game {
+                #[mlua_bindgen(partial = combat)]
+                impl LuaPlayer {
+                    #[method]
+                    fn attack(this: &Self) {}
+                }
+
+                #[mlua_bindgen(name = "Hero", include = [combat])]
+                impl LuaPlayer {
+                    #[get]
+                    fn level(this: &Self) -> u32 {}
+
+                    #[func]
+                    fn new() -> Self {}
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        // Both impl blocks are declared as one type, named by the main block
+        assert_eq!(module.impls.len(), 1);
+        let player = &module.impls[0];
+        assert_eq!(player.name, "Hero".to_owned());
+        assert_eq!(player.methods[0].name, "attack".to_owned());
+        assert_eq!(player.fields[0].name, "level".to_owned());
+        assert_eq!(player.funcs[0].name, "new".to_owned());
+        Ok(())
+    }
+
+    #[test]
+    fn generic_impls() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod assets {
+                #[mlua_bindgen(instantiate(MeshHandle(T = Mesh), TextHandle(T = String)), include = [loading])]
+                impl<T: Clone> Handle<T> {
+                    #[func]
+                    fn new(value: T) -> Self {}
+
+                    #[get]
+                    fn value(this: &Self) -> T {}
+                }
+
+                #[mlua_bindgen(partial = loading)]
+                impl<T: Clone> Handle<T> {
+                    #[method]
+                    fn reload(this: &Self) -> Option<T> {}
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        // Every instance is declared as a separate type, along with the parts
+        let handles: Vec<String> = module
+            .impls
+            .iter()
+            .map(|handle| {
+                let (value, reload) = (&handle.fields[0], &handle.methods[0]);
+                let new = handle.funcs[0].as_ty_impl(false);
+                format!("{} {}{} {}", handle.name, value.ty, reload.return_ty, new)
+            })
+            .collect();
+        assert_eq!(
+            handles,
+            vec![
+                "MeshHandle uMesh: uMesh | nil function(value: uMesh): uMeshHandle",
+                "TextHandle string: string | nil function(value: string): uTextHandle",
+            ]
+        );
         Ok(())
     }
+
+    #[test]
+    fn derived_meta() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod units {
+                #[mlua_bindgen(meta(add, eq, unm, tostring, len))]
+                impl LuaMeters {}
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        let meters = &module.impls[0];
+        let declare = |funcs: &[LuaFunc], is_method: bool| -> Vec<String> {
+            funcs
+                .iter()
+                .map(|func| format!("{}: {}", func.name, func.as_ty_impl(is_method)))
+                .collect()
+        };
+        assert_eq!(
+            declare(&meters.meta_funcs, false),
+            vec![
+                "__add: function(a: uMeters, b: uMeters): uMeters",
+                "__eq: function(a: uMeters, b: uMeters): boolean",
+                "__unm: function(value: uMeters): uMeters",
+            ]
+        );
+        assert_eq!(
+            declare(&meters.meta_methods, true),
+            vec![
+                "__tostring: function(self): string",
+                "__len: function(self): number"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn registration_kinds() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod world {
+                #[mlua_bindgen]
+                impl LuaTile {
+                    #[function]
+                    fn describe(ud: AnyUserData, prefix: String) -> String {}
+
+                    #[meta_method]
+                    fn __len(this: &Self) -> usize {}
+
+                    #[get_function]
+                    fn kind(ud: AnyUserData) -> String {}
+
+                    #[set_function]
+                    fn set_solid(ud: AnyUserData, solid: bool) {}
+
+                    #[static_field]
+                    fn size() -> u32 {}
+
+                    #[meta_field]
+                    fn __name() -> String {}
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        let tile = &module.impls[0];
+        assert_eq!(
+            tile.functions[0].as_ty_impl(false),
+            "function(ud: userdata, prefix: string): string"
+        );
+        assert!(tile.methods.is_empty());
+        assert_eq!(tile.meta_methods[0].name, "__len");
+
+        // Field functions are declared as fields, since the userdata argument is passed by mlua
+        let fields: Vec<String> = tile
+            .fields
+            .iter()
+            .map(|field| format!("{}: {}", field.name, field.ty))
+            .collect();
+        assert_eq!(fields, vec!["kind: string", "size: number"]);
+        Ok(())
+    }
+
+    #[test]
+    fn constructors() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod math {
+                #[mlua_bindgen]
+                impl LuaVector {
+                    #[constructor(table)]
+                    fn new(x: f32, #[default(0.0)] y: f32) -> Self {}
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        let vector = &module.impls[0];
+        let constructors: Vec<String> = vector
+            .constructors
+            .iter()
+            .map(|func| func.as_ty_impl(true))
+            .collect();
+        assert_eq!(
+            constructors,
+            vec![
+                "function(self, x: number, y?: number): uVector",
+                "function(self, args: {x: number, y?: number}): uVector",
+            ]
+        );
+        assert!(vector.funcs.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn constants() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod math {
+                #[mlua_bindgen(rename_all = "camelCase")]
+                impl LuaVector {
+                    #[constant]
+                    const ZERO: Self = Self::new(0.0, 0.0);
+
+                    #[constant(field)]
+                    #[mlua_bindgen(name = "DIMS")]
+                    const DIMENSIONS: u32 = 2;
+
+                    const HIDDEN: u32 = 0;
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        let vector = &module.impls[0];
+        let declare = |fields: &[LuaField]| -> Vec<String> {
+            fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name, field.ty))
+                .collect()
+        };
+        assert_eq!(
+            declare(&vector.constants),
+            vec!["ZERO: uVector", "DIMS: number"]
+        );
+        assert_eq!(declare(&vector.fields), vec!["DIMS: number"]);
+        Ok(())
+    }
+
+    #[test]
+    fn proxies() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod shapes {
+                #[mlua_bindgen]
+                impl Circle {
+                    #[func]
+                    fn new(radius: f64) -> Self {}
+
+                    #[constant]
+                    const UNIT: f64 = 1.0;
+                }
+
+                #[mlua_bindgen(as_table)]
+                impl Square {
+                    #[func]
+                    fn new(side: f64) -> Self {}
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        // The class of a proxy is the userdata type itself
+        let (circle_ty, circle) = module.impls[0].lua_expand(true);
+        assert_eq!(
+            circle_ty,
+            "global type uCircle = record\n    UNIT: number\n    new: function(radius: number): uCircle\nend\n"
+        );
+        assert_eq!(circle, "Circle: uCircle\n");
+
+        let (square_ty, square) = module.impls[1].lua_expand(true);
+        assert_eq!(square_ty, "global type uSquare = record\nend\n");
+        assert_eq!(
+            square,
+            "record Square\n    new: function(side: number): uSquare\nend\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn remote_types() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod math {
+                #[mlua_bindgen(remote = glam::Vec3)]
+                impl Vec3Def {
+                    #[func]
+                    fn new(x: f32, y: f32, z: f32) -> glam::Vec3 {}
+
+                    #[method]
+                    fn length(&self) -> f32 {}
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        // Remote types are declared under the foreign type, and their class is always a table
+        let (vec3_ty, vec3) = module.impls[0].lua_expand(true);
+        assert_eq!(
+            vec3_ty,
+            "global type uVec3 = record\n    length: function(self): number\nend\n"
+        );
+        assert_eq!(
+            vec3,
+            "record Vec3\n    new: function(x: number, y: number, z: number): uVec3\nend\n"
+        );
+
+        // Bodies are pasted as they are, so `Self` in them would be the impl type. Nested items have their own
+        let parse = |item: ItemMod| parse_mod(ItemAttributes::empty(), item, true).map(|_| ());
+        let self_body = parse(parse_quote! {
+            mod math {
+                #[mlua_bindgen(remote = Vec3)]
+                impl Vec3Def {
+                    #[method]
+                    fn doubled(&self) -> Self {
+                        Self::new(self.x * 2.0, self.y * 2.0, self.z * 2.0)
+                    }
+                }
+            }
+        });
+        assert_eq!(
+            self_body.unwrap_err().to_string(),
+            "`Self` is `Vec3Def` in the bodies of remote impl blocks, so the remote type has to be named instead (i.e. `Vec3`)"
+        );
+        parse(parse_quote! {
+            mod math {
+                #[mlua_bindgen(remote = Vec3)]
+                impl Vec3Def {
+                    #[method]
+                    fn sum(&self) -> f32 {
+                        struct Sum(f32);
+                        impl Sum {
+                            fn new(value: f32) -> Self {
+                                Self(value)
+                            }
+                        }
+                        Sum::new(self.x + self.y + self.z).0
+                    }
+                }
+            }
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn extended_types() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod scene {
+                #[mlua_bindgen]
+                impl Node {
+                    #[get]
+                    fn name(&self) -> String {}
+
+                    #[method]
+                    fn path(&self) -> String {}
+
+                    #[meta]
+                    fn __tostring(&self) -> String {}
+                }
+
+                #[mlua_bindgen(extends = Node, via = Sprite::node, via_mut = Sprite::node_mut)]
+                impl Sprite {
+                    #[get]
+                    fn frame(&self) -> u32 {}
+
+                    /// Shadows the method of `Node`
+                    #[method]
+                    fn path(&self) -> String {}
+                }
+
+                #[mlua_bindgen(
+                    extends = LuaSprite,
+                    via = AnimatedSprite::sprite,
+                    via_mut = AnimatedSprite::sprite_mut
+                )]
+                impl AnimatedSprite {
+                    #[method]
+                    fn play(&mut self) {}
+                }
+            }
+        };
+        let mut module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+        resolve_bases(&mut [&mut module])?;
+
+        let declare = |ty_name: &str| -> Vec<String> {
+            let lua_struct = module
+                .impls
+                .iter()
+                .find(|lua_struct| lua_struct.ty_name == ty_name)
+                .unwrap();
+            let fields = lua_struct.fields.iter().map(|field| field.name.clone());
+            let methods = lua_struct.methods.iter().map(|method| method.name.clone());
+            fields.chain(methods).collect()
+        };
+        assert_eq!(declare("Sprite"), vec!["frame", "name", "path"]);
+        assert_eq!(
+            declare("AnimatedSprite"),
+            vec!["frame", "name", "play", "path"]
+        );
+        assert!(module.impls[1].meta_methods.is_empty());
+
+        let unknown: ItemMod = parse_quote! {
+            mod scene {
+                #[mlua_bindgen(extends = Node, via = Sprite::node, via_mut = Sprite::node_mut)]
+                impl Sprite {}
+            }
+        };
+        let mut module =
+            LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), unknown, true)?)?;
+        assert!(resolve_bases(&mut [&mut module]).is_err());
+
+        let no_accessor: ItemMod = parse_quote! {
+            mod scene {
+                #[mlua_bindgen(extends = Node, via = Sprite::node)]
+                impl Sprite {}
+            }
+        };
+        assert_eq!(
+            parse_mod(ItemAttributes::empty(), no_accessor, true)
+                .map(|_| ())
+                .unwrap_err()
+                .to_string(),
+            "`extends` needs both accessors of the base (i.e. `via = Sprite::node, via_mut = Sprite::node_mut`)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn meta_signatures() -> Result<(), Error> {
+        let item: ItemMod = parse_quote! {
+            mod math {
+                #[mlua_bindgen]
+                impl LuaVec {
+                    #[meta]
+                    fn __add(a: Self, b: Self) -> Self {}
+
+                    #[meta]
+                    fn __eq(&self, other: Self) -> bool {}
+
+                    #[meta]
+                    fn __index(&self, key: String) -> Option<f64> {}
+
+                    #[meta]
+                    fn __call(&mut self, args: Variadic<f64>) -> f64 {}
+
+                    #[meta]
+                    fn __concat(a: Self, b: MySelfData) -> SelfRef {}
+
+                    #[get]
+                    fn origin(&self) -> SelfRef {}
+                }
+            }
+        };
+        let module = LuaModule::from_parsed(parse_mod(ItemAttributes::empty(), item, true)?)?;
+
+        let vec = &module.impls[0];
+        assert_eq!(
+            vec.meta_funcs[0].as_meta_ty(&vec.ty_name, false),
+            "function(a: uVec, b: uVec): uVec"
+        );
+        // Only `Self` itself is the owning type, not the types that have it in their names
+        assert_eq!(
+            vec.meta_funcs[1].as_meta_ty(&vec.ty_name, false),
+            "function(a: uVec, b: uMySelfData): uSelfRef"
+        );
+        assert_eq!(vec.fields[0].ty.to_string(), "uSelfRef");
+        let meta_methods: Vec<String> = vec
+            .meta_methods
+            .iter()
+            .map(|func| func.as_meta_ty(&vec.ty_name, true))
+            .collect();
+        assert_eq!(
+            meta_methods,
+            vec![
+                "function(self: uVec, other: uVec): boolean",
+                "function(self: uVec, key: string): number | nil",
+                "function(self: uVec, ...: number): number",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn meta_validation() {
+        let parse = |item: ItemMod| parse_mod(ItemAttributes::empty(), item, true).map(|_| ());
+
+        let typo = parse(parse_quote! {
+            mod math {
+                #[mlua_bindgen]
+                impl LuaVec {
+                    #[meta]
+                    fn __tostrng(&self) -> String {}
+                }
+            }
+        });
+        assert_eq!(
+            typo.unwrap_err().to_string(),
+            "`__tostrng` is not a Lua metamethod. Did you mean `__tostring`?"
+        );
+
+        let comparison = parse(parse_quote! {
+            mod math {
+                #[mlua_bindgen]
+                impl LuaVec {
+                    #[meta]
+                    fn __lt(&self, other: Self) -> Self {}
+                }
+            }
+        });
+        assert_eq!(
+            comparison.unwrap_err().to_string(),
+            "`__lt` has to return `bool`"
+        );
+
+        let arity = parse(parse_quote! {
+            mod math {
+                #[mlua_bindgen]
+                impl LuaVec {
+                    #[meta]
+                    fn __add(&self) -> Self {}
+                }
+            }
+        });
+        assert_eq!(
+            arity.unwrap_err().to_string(),
+            "Lua calls `__add` with 2 operands (the userdata included), but it takes 1"
+        );
+
+        let reserved = parse(parse_quote! {
+            mod math {
+                #[mlua_bindgen]
+                impl LuaVec {
+                    #[meta]
+                    fn __gc(&self) {}
+                }
+            }
+        });
+        assert!(reserved.is_err());
+    }
 }
@@ -1,5 +1,6 @@
 //! Crate errors
 
+#[cfg(feature="bindgen")]
 use std::fmt::{Debug, Display};
 
 #[cfg(feature="bindgen")]
@@ -1,5 +1,7 @@
 pub use macros::{mlua_bindgen, mlua_bindgen_ignore};
 
+#[doc(hidden)]
+pub mod __private;
 #[cfg(feature = "bindgen")]
 pub mod bindgen;
-pub mod error;
\ No newline at end of file
+pub mod error;
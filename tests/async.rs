@@ -0,0 +1,107 @@
+#![cfg(feature = "async")]
+
+use mlua::FromLua;
+use mlua_bindgen::mlua_bindgen;
+
+#[mlua_bindgen]
+pub async fn double(_: mlua::Lua, val: u32) -> u32 {
+    val * 2
+}
+
+#[derive(FromLua, Clone)]
+pub struct Counter {
+    value: u32,
+}
+
+#[mlua_bindgen]
+impl Counter {
+    #[func]
+    async fn new(_: _, value: u32) -> Self {
+        Self { value }
+    }
+
+    #[method]
+    async fn get(_: _, this: &Self) -> u32 {
+        this.value
+    }
+
+    #[method_mut]
+    async fn add(_: _, this: &mut Self, #[default(1)] by: u32) {
+        this.value += by;
+    }
+
+    #[method_mut]
+    async fn add_str(this: &mut Self, by: String) -> Result<u32, std::num::ParseIntError> {
+        this.value += by.parse::<u32>()?;
+        Ok(this.value)
+    }
+
+    #[method]
+    async fn doubled(&self) -> u32 {
+        self.value * 2
+    }
+
+    #[function]
+    async fn value_of(_: mlua::Lua, ud: mlua::AnyUserData) -> mlua::Result<u32> {
+        Ok(ud.borrow::<Self>()?.value)
+    }
+}
+
+#[mlua_bindgen]
+mod net {
+    use mlua_bindgen::mlua_bindgen;
+
+    #[mlua_bindgen]
+    pub async fn lua_fetch(_: mlua::Lua, url: String) -> String {
+        format!("<{url}>")
+    }
+
+    /// Returns from the async body, just like from an async fn
+    #[mlua_bindgen]
+    pub async fn echo(_: mlua::Lua, text: String) -> impl mlua::IntoLua {
+        if text.is_empty() {
+            return "silence".to_owned();
+        }
+        text
+    }
+
+    #[mlua_bindgen(keep)]
+    pub async fn ping(times: u32) -> mlua::Result<String> {
+        Ok("pong".repeat(times as usize))
+    }
+}
+
+#[test]
+fn async_bindings() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals()
+        .set("double", lua.create_async_function(double)?)?;
+    lua.globals().set("Counter", Counter::as_table(&lua)?)?;
+
+    let res = futures::executor::block_on(
+        lua.load(
+            "
+            local counter = Counter.new(double(5))
+            counter:add()
+            counter:add(1)
+            assert(counter:doubled() == 24 and counter:value_of() == 12)
+            assert(not pcall(counter.add_str, counter, 'two'))
+            return counter:add_str('2')
+        ",
+        )
+        .eval_async::<u32>(),
+    )?;
+    assert_eq!(res, 14);
+
+    lua.globals().set("net", net_module(&lua)?)?;
+    let res = futures::executor::block_on(
+        lua.load("return net.fetch('page') .. net.ping(2)").eval_async::<String>(),
+    )?;
+    assert_eq!(res, "<page>pongpong");
+    let res = futures::executor::block_on(
+        lua.load("return net.echo('') .. net.echo('!')").eval_async::<String>(),
+    )?;
+    assert_eq!(res, "silence!");
+
+    Ok(())
+}
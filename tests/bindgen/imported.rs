@@ -17,19 +17,17 @@ pub mod imported {
     impl LuaConflictingType {
         #[func]
         pub fn create(_: &mlua::Lua) -> Self {
-            Ok(Self(ConflictingType { something: 500 }))
+            Self(ConflictingType { something: 500 })
         }   
 
         #[method]
         pub fn conflict(_: &mlua::Lua, this: &Self) {
             // Idk, doing some conflicting?
-            Ok(())
         }
     }
 
     #[mlua_bindgen]
     pub fn lua_conflicting_func(_: &mlua::Lua, val: String) {
         conflicting_func(val);
-        Ok(())
     }
 }
\ No newline at end of file
@@ -9,9 +9,7 @@ static COUNTER: AtomicU32 = AtomicU32::new(5);
 #[mlua_bindgen]
 mod ignored_inner {
     #[mlua_bindgen]
-    pub fn even_sneakier(_: &mlua::Lua) {
-        Ok(())
-    }
+    pub fn even_sneakier(_: &mlua::Lua) {}
 }
 
 /// This module and its items should be ignored
@@ -20,9 +18,7 @@ mod ignored_inner {
 mod ignored {
 
     #[mlua_bindgen]
-    pub fn ignored_function(_: &mlua::Lua) {
-        Ok(())
-    }
+    pub fn ignored_function(_: &mlua::Lua) {}
 }
 
 #[mlua_bindgen]
@@ -31,16 +27,90 @@ mod super_inner {
 
     #[mlua_bindgen]
     pub fn add(_: &mlua::Lua, val1: f32, val2: f32) -> f32 {
-        Ok(val1 + val2)
+        val1 + val2
     }
 
     #[mlua_bindgen]
     pub fn subtract(_: &mlua::Lua, val1: f32, val2: f32) -> f32 {
-        Ok(val1 - val2)
+        val1 - val2
+    }
+
+    /// A kept function should be declared with its inner result type
+    #[mlua_bindgen(keep)]
+    pub fn halve(val: f32) -> mlua::Result<f32> {
+        Ok(val / 2.0)
+    }
+
+    /// Errors are raised in Lua, so this is declared as returning a number
+    #[mlua_bindgen]
+    pub fn parse_num(text: String) -> Result<f32, std::num::ParseFloatError> {
+        text.parse()
+    }
+
+    /// The last argument can be omitted
+    #[mlua_bindgen]
+    pub fn clamp(val: f32, #[default(0.0)] min: f32, #[default(1.0)] max: f32) -> f32 {
+        val.clamp(min, max)
+    }
+
+    /// Called with named arguments
+    #[mlua_bindgen(table_args)]
+    pub fn rect_area(width: f32, height: f32, #[default(1.0)] scale: f32) -> f32 {
+        width * height * scale
+    }
+
+    /// Generic functions without instantiations are declared as generic
+    #[mlua_bindgen]
+    pub fn echo<T: FromLua + IntoLua>(val: T) -> T {
+        val
+    }
+
+    /// Instantiated functions are declared once per instance
+    #[mlua_bindgen(instantiate(int_max(T = i64), str_max(T = String)))]
+    pub fn max_of<T: PartialOrd>(a: T, b: T) -> T {
+        if a > b { a } else { b }
+    }
+
+    /// Arguments with checks
+    #[mlua_bindgen]
+    pub fn percent(#[range(0.0..=1.0)] val: f32, #[non_empty] #[len(max = 3)] suffix: String) -> String {
+        format!("{}{suffix}", val * 100.0)
+    }
+
+    /// Takes any amount of numbers
+    #[mlua_bindgen]
+    pub fn max(first: f32, rest: mlua::Variadic<f32>) -> f32 {
+        rest.into_iter().fold(first, f32::max)
+    }
+
+    /// Returns its arguments back
+    #[mlua_bindgen]
+    pub fn identity(values: mlua::MultiValue) -> mlua::MultiValue {
+        values
+    }
+
+    /// Declares the types of its callback and the returned table
+    #[mlua_bindgen]
+    #[lua_return("{string}")]
+    pub fn filter(
+        lua: &mlua::Lua,
+        #[lua_type("{string}")] items: mlua::Table,
+        #[lua_type("function(string): boolean")] pred: mlua::Function,
+    ) -> mlua::Table {
+        items
+    }
+
+    /// Doesn't take the Lua state at all
+    #[mlua_bindgen]
+    pub fn negate(val: f32) -> f32 {
+        -val
     }
 
+    /// Struct fields are declared in the userdata type of the impl below
+    #[mlua_bindgen]
     #[derive(Clone, Debug, FromLua, PartialEq)]
     pub struct CoolNumber {
+        #[lua(get, set)]
         val: f64
     }
 
@@ -50,19 +120,84 @@ mod super_inner {
         }
     }
 
-    #[mlua_bindgen]
+    #[mlua_bindgen(meta(eq))]
     impl CoolNumber {
         #[func]
         fn new(_: _, val: mlua::Either<f32, Self>) -> Self {
-            Ok(match val {
+            match val {
                 mlua::Either::Left(num) => Self::new(val),
                 mlua::Either::Right(other) => Self::new(other.val)
-            })
+            }
         }
 
         #[get]
         fn value(_: _, this: &Self) -> f32 {
-            Ok(this.val as f32)
+            this.val as f32
+        }
+
+        #[method]
+        fn is_zero(this: &Self) -> bool {
+            this.val == 0.0
+        }
+
+        /// Methods with receivers are declared like the rest
+        #[method]
+        fn floor(&self) -> f64 {
+            self.val.floor()
+        }
+
+        #[meta]
+        fn __len(&self) -> usize {
+            self.val.to_string().len()
+        }
+
+        /// Plain Rust functions aren't declared
+        pub fn is_whole(&self) -> bool {
+            self.val.fract() == 0.0
+        }
+
+        /// Declared under its Lua name
+        #[method]
+        #[mlua_bindgen(name = "isNegative")]
+        fn is_negative(this: &Self) -> bool {
+            this.val < 0.0
+        }
+
+        #[get]
+        #[lua_type("{number}")]
+        fn digits(lua: _, this: &Self) -> mlua::Result<mlua::Table> {
+            lua.create_sequence_from(this.val.to_string().chars().filter_map(|c| c.to_digit(10)))
+        }
+
+        /// Shared by all numbers
+        #[static_field]
+        fn precision() -> u32 {
+            32
+        }
+
+        /// Receives the number as `AnyUserData`
+        #[function]
+        fn copy(ud: mlua::AnyUserData) -> mlua::Result<Self> {
+            Ok(ud.borrow::<Self>()?.clone())
+        }
+    }
+
+    pub struct Range<T> {
+        start: T,
+        stop: T,
+    }
+
+    /// Declared once per instance
+    #[mlua_bindgen(instantiate(IntRange(T = i64), NumberRange(T = f64)))]
+    impl<T: FromLua + IntoLua + Copy + PartialOrd> Range<T> {
+        #[func]
+        fn new(start: T, stop: T) -> Self {
+            Self { start, stop }
+        }
+
+        #[method]
+        fn contains(&self, value: T) -> bool {
+            self.start <= value && value < self.stop
         }
     }
 }
@@ -73,7 +208,7 @@ mod inner {
 
     #[mlua_bindgen]
     pub fn mul(_: &mlua::Lua, val1: f32, val2: f32) -> f32 {
-        Ok(val1 * val2)
+        val1 * val2
     }
 
     #[mlua_bindgen]
@@ -88,7 +223,47 @@ mod inner {
     /// Adds something to a global counter
     #[mlua_bindgen]
     pub fn do_something(_: &mlua::Lua, what: u32) -> f32 {
-        Ok(0.75)
+        0.75
+    }
+
+    /// Renamed functions are declared under their Lua names
+    #[mlua_bindgen(name = "divideBy")]
+    pub fn lua_div(val: f32, by: f32) -> f32 {
+        val / by
+    }
+
+    /// Async functions are declared just like normal ones
+    #[mlua_bindgen]
+    pub async fn fetch(_: mlua::Lua, url: String) -> String {
+        url
+    }
+
+    pub struct Particle {
+        pos: crate::main::Vector,
+        life: f32,
+    }
+
+    impl Particle {
+        fn pos(&self) -> &crate::main::Vector {
+            &self.pos
+        }
+
+        fn pos_mut(&mut self) -> &mut crate::main::Vector {
+            &mut self.pos
+        }
+    }
+
+    /// Declares the fields and methods of `Vector` as well, even though it's in another module
+    #[mlua_bindgen(
+        extends = crate::main::Vector,
+        via = Particle::pos,
+        via_mut = Particle::pos_mut
+    )]
+    impl Particle {
+        #[get]
+        fn life(&self) -> f32 {
+            self.life
+        }
     }
 }
 
@@ -124,48 +299,54 @@ mod main {
     impl Vector {
         #[func]
         fn new(_: _, x: f32, y: f32) -> Self {
-            Ok(Self::new(x, y))
+            Self::new(x, y)
+        }
+
+        #[constant]
+        const ZERO: Self = Self { x: 0.0, y: 0.0 };
+
+        /// `Vector(1, 2)` or `Vector{ x = 1, y = 2 }`
+        #[constructor(table)]
+        fn create(x: f32, y: f32) -> Self {
+            Self::new(x, y)
         }
 
         #[meta]
         fn __add(_: _, this: Self, with: Self) -> Self {
-            Ok(Self {
+            Self {
                 x: this.x + with.x,
                 y: this.x + with.x
-            })
+            }
         }
 
         #[meta]
         fn __tostring(_: _, this: Self) -> String {
-            Ok(format!("<Vector x={}, y={}>", this.x, this.y))
+            format!("<Vector x={}, y={}>", this.x, this.y)
         }
 
         #[method]
         fn hello(_: _, this: &Self) {
             // Do something
-            Ok(())
         }
 
         #[get]
         fn x(_: _, this: &Self) -> f32 {
-            Ok(this.x)
+            this.x
         }
 
         #[get]
         fn y(_: _, this: &Self) -> f32 {
-            Ok(this.y)
+            this.y
         }
 
         #[set]
         fn x(_: _, this: &mut Self, to: f32) {
             this.x = to;
-            Ok(())
         }
 
         #[set]
         fn y(_: _, this: &mut Self, to: f32) {
             this.y = to;
-            Ok(())
         }
     }
 
@@ -181,20 +362,20 @@ mod main {
     /// Should return a table of strings
     #[mlua_bindgen]
     pub fn do_something_better(_: &mlua::Lua, what: u32, other: String) -> [String; 3] {
-        Ok(["".to_owned(), "".to_owned(), "".to_owned()])
+        ["".to_owned(), "".to_owned(), "".to_owned()]
     }
 
     /// The same
     #[mlua_bindgen]
     pub fn do_something_better_vec(_: &mlua::Lua, what: u32, other: String) -> Vec<String> {
-        Ok(vec!["".to_owned(), "".to_owned(), "".to_owned()])
+        vec!["".to_owned(), "".to_owned(), "".to_owned()]
     }
 
     /// This function should not be in the generated bindings
     #[mlua_bindgen_ignore]
     #[mlua_bindgen]
     pub fn require(_: &mlua::Lua, module: String) -> Table {
-        Ok(())
+        unimplemented!()
     }
 }
 
@@ -2,9 +2,172 @@ use macros::mlua_bindgen;
 
 #[mlua_bindgen]
 pub fn cool_fn(_: &mlua::Lua, sm: u32, is_true: bool) -> u32 {
-    assert_eq!(is_true, true);
+    assert!(is_true);
     assert_eq!(sm, 32);
-    Ok(50)
+    50
+}
+
+/// Functions that don't need the Lua state can omit it
+#[mlua_bindgen]
+pub fn add(a: f32, b: f32) -> f32 {
+    a + b
+}
+
+/// A kept function stays callable from Rust, while Lua uses the generated `scale_lua` adapter
+#[mlua_bindgen(keep)]
+pub fn scale<T: Into<f64>>(val: T, by: f64) -> mlua::Result<f64> {
+    Ok(val.into() * by)
+}
+
+/// Generic functions are registered with concrete types (i.e. `pick::<i64>`)
+#[mlua_bindgen]
+pub fn pick<T: mlua::IntoLua>(first: bool, a: T, b: T) -> T {
+    if first {
+        a
+    } else {
+        b
+    }
+}
+
+/// Lua types for bindgen are only declared, so they don't change the function
+#[mlua_bindgen]
+#[lua_return("{string}")]
+pub fn keys(
+    lua: &mlua::Lua,
+    #[lua_type("{[string]: any}")] table: mlua::Table,
+) -> mlua::Result<mlua::Table> {
+    let keys = table.pairs::<String, mlua::Value>().filter_map(|pair| pair.ok());
+    lua.create_sequence_from(keys.map(|(key, _)| key))
+}
+
+#[mlua_bindgen(keep)]
+pub fn globals_len(lua: &mlua::Lua) -> mlua::Result<usize> {
+    Ok(lua.globals().pairs::<mlua::Value, mlua::Value>().count())
+}
+
+/// Functions can also return a result with a standard error, which is raised in Lua
+#[mlua_bindgen]
+pub fn parse_int(text: String) -> Result<i64, std::num::ParseIntError> {
+    text.parse()
+}
+
+/// Errors that can be converted into `mlua::Error` directly
+pub struct OutOfRange;
+
+impl From<OutOfRange> for mlua::Error {
+    fn from(_: OutOfRange) -> Self {
+        mlua::Error::runtime("value is out of range")
+    }
+}
+
+/// `mlua::Error` is a standard error as well (with `error-send`), but it's raised as it is instead of being wrapped
+#[mlua_bindgen]
+pub fn fail(message: String) -> mlua::Result<u32> {
+    Err(mlua::Error::runtime(message))
+}
+
+/// Standard errors that can also be converted into `mlua::Error` are converted directly
+#[derive(Debug)]
+pub struct Overflow;
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "overflow")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+impl From<Overflow> for mlua::Error {
+    fn from(_: Overflow) -> Self {
+        mlua::Error::runtime("the sum doesn't fit")
+    }
+}
+
+#[mlua_bindgen]
+pub fn checked_add(a: u8, b: u8) -> Result<u8, Overflow> {
+    a.checked_add(b).ok_or(Overflow)
+}
+
+/// Rewritten functions with plain return types always succeed, and return their values as they are (early
+/// returns included)
+#[mlua_bindgen]
+pub fn clamp_len(text: String, max: usize) -> usize {
+    if text.len() > max {
+        return max;
+    }
+    text.len()
+}
+
+/// `impl Trait` return types work as well, with early returns and `?`
+#[mlua_bindgen]
+pub fn describe_len(text: String) -> impl mlua::IntoLua {
+    if text.is_empty() {
+        return "empty".to_owned();
+    }
+    format!("{} chars", text.len())
+}
+
+#[mlua_bindgen]
+pub fn parse_twice(text: String) -> Result<impl mlua::IntoLua, std::num::ParseIntError> {
+    let value: i64 = text.parse()?;
+    Ok(value * 2)
+}
+
+/// Kept functions can return plain values, which always succeed...
+#[mlua_bindgen(keep)]
+pub fn double(val: i64) -> i64 {
+    val * 2
+}
+
+/// ...or results with any supported error
+#[mlua_bindgen(keep)]
+pub fn to_percent(val: f64) -> Result<u8, OutOfRange> {
+    if !(0.0..=1.0).contains(&val) {
+        return Err(OutOfRange);
+    }
+    Ok((val * 100.0) as u8)
+}
+
+#[cfg(feature = "anyhow")]
+#[mlua_bindgen(keep)]
+pub fn checked_div(a: i64, b: i64) -> anyhow::Result<i64> {
+    a.checked_div(b).ok_or_else(|| anyhow::anyhow!("division by zero"))
+}
+
+/// Arguments with default values are optional in Lua
+#[mlua_bindgen]
+pub fn lerp(a: f64, b: f64, #[default(0.5)] t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+#[mlua_bindgen(keep)]
+pub fn greet(#[default(String::from("world"))] name: String) -> String {
+    format!("hello {name}")
+}
+
+/// Table argument functions are called with a single table of named arguments
+#[mlua_bindgen(table_args)]
+pub fn spawn(x: f32, y: f32, name: String, title: Option<String>, #[default(1)] level: u32) -> String {
+    let title = title.map(|title| format!(" the {title}")).unwrap_or_default();
+    format!("{name}{title} ({level}) at {x}, {y}")
+}
+
+#[mlua_bindgen(keep, table_args)]
+pub fn area(width: f64, height: f64) -> f64 {
+    width * height
+}
+
+/// A trailing variadic argument takes all the remaining values
+#[mlua_bindgen]
+pub fn sum(start: f64, nums: mlua::Variadic<f64>) -> f64 {
+    nums.iter().fold(start, |acc, num| acc + num)
+}
+
+/// Variadic values can be returned as well
+#[mlua_bindgen(keep)]
+pub fn split(text: String, sep: String) -> mlua::Variadic<String> {
+    text.split(sep.as_str()).map(str::to_owned).collect()
 }
 
 #[test]
@@ -14,5 +177,195 @@ fn functions() -> mlua::Result<()> {
     let res = func.call::<u32>((32, true))?;
     assert_eq!(res, 50);
 
+    let add = lua.create_function(add)?;
+    assert_eq!(add.call::<f32>((2.0, 3.5))?, 5.5);
+
+    // Kept functions can be used both from Rust and Lua
+    assert_eq!(scale(2.0f32, 4.0)?, 8.0);
+    let scale = lua.create_function(scale_lua::<f64>)?;
+    assert_eq!(scale.call::<f64>((2.5, 2.0))?, 5.0);
+
+    let keys = lua.create_function(keys)?;
+    let table = lua.create_table_from([("key", 1)])?;
+    assert_eq!(keys.call::<Vec<String>>(table)?, vec!["key".to_owned()]);
+
+    let pick = lua.create_function(pick::<String>)?;
+    assert_eq!(pick.call::<String>((false, "a", "b"))?, "b");
+
+    let lua_globals_len = lua.create_function(globals_len_lua)?;
+    assert_eq!(lua_globals_len.call::<usize>(())?, globals_len(&lua)?);
+
+    Ok(())
+}
+
+#[test]
+fn defaults() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("lerp", lua.create_function(lerp)?)?;
+    lua.globals().set("greet", lua.create_function(greet_lua)?)?;
+
+    lua.load(
+        "
+        assert(lerp(0, 10) == 5)
+        assert(lerp(0, 10, 0.1) == 1)
+        assert(greet() == 'hello world')
+        assert(greet('lua') == 'hello lua')
+    ",
+    )
+    .exec()?;
+
+    // Kept functions take their arguments as usual from Rust
+    assert_eq!(greet("rust".to_owned()), "hello rust");
+    Ok(())
+}
+
+#[test]
+fn table_args() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("spawn", lua.create_function(spawn)?)?;
+    lua.globals().set("area", lua.create_function(area_lua)?)?;
+
+    lua.load(
+        "
+        assert(spawn{ x = 1, y = 2, name = 'orc' } == 'orc (1) at 1, 2')
+        assert(spawn{ x = 1, y = 2, name = 'orc', title = 'brave', level = 3 } == 'orc the brave (3) at 1, 2')
+        assert(area{ width = 2, height = 3 } == 6)
+    ",
+    )
+    .exec()?;
+
+    // Bad fields are named in the error
+    let err = lua
+        .load("spawn{ x = 1, y = 'up', name = 'orc' }")
+        .exec()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("bad argument `y` to `spawn")
+            && err.contains("error converting Lua string to f32")
+    );
+
+    assert_eq!(area(2.0, 2.0), 4.0);
+    Ok(())
+}
+
+#[test]
+fn argument_errors() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("add", lua.create_function(add)?)?;
+    lua.globals().set("sum", lua.create_function(sum)?)?;
+    lua.globals().set("area", lua.create_function(area_lua)?)?;
+
+    // Failed conversions name the function, and the position and name of the argument (the function is followed by
+    // the location of the binding with `error-location`)
+    let error = |code: &str| lua.load(code).exec().unwrap_err().to_string();
+    assert!(error("add(1, 'x')").contains("bad argument `b` to `add"));
+    assert!(error("add(1)").contains("error converting Lua nil to f32"));
+    assert!(error("sum(1, 2, 'x')").contains("bad argument `nums` to `sum"));
+    assert!(error("area(5)").contains("bad argument #1 to `area"));
+
+    // The conversion error is kept as the cause
+    let cause = match lua.load("add(1, 'x')").exec().unwrap_err() {
+        mlua::Error::CallbackError { cause, .. } => cause,
+        err => panic!("expected a callback error, got {err:?}"),
+    };
+    match cause.as_ref() {
+        mlua::Error::BadArgument {
+            pos, name, cause, ..
+        } => {
+            assert_eq!((*pos, name.as_deref()), (2, Some("b")));
+            assert!(matches!(
+                cause.as_ref(),
+                mlua::Error::FromLuaConversionError { from: "string", .. }
+            ));
+        }
+        err => panic!("expected a bad argument error, got {err:?}"),
+    }
+
+    // The binding's location is included with the `error-location` feature
+    #[cfg(feature = "error-location")]
+    assert!(error("add(1, 'x')").contains("to `add (at tests/funcs.rs:"));
+
+    Ok(())
+}
+
+#[test]
+fn variadics() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("sum", lua.create_function(sum)?)?;
+    lua.globals().set("split", lua.create_function(split_lua)?)?;
+
+    lua.load(
+        "
+        assert(sum(1) == 1)
+        assert(sum(1, 2, 3, 4) == 10)
+
+        local a, b, c = split('a,b,c', ',')
+        assert(a == 'a' and b == 'b' and c == 'c')
+        assert(select('#', split('a,b', ',')) == 2)
+    ",
+    )
+    .exec()?;
+
+    Ok(())
+}
+
+#[test]
+fn return_types() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+
+    let clamp_len = lua.create_function(clamp_len)?;
+    assert_eq!(clamp_len.call::<usize>(("four", 8))?, 4);
+    assert_eq!(clamp_len.call::<usize>(("twelve chars", 8))?, 8);
+
+    let parse_int = lua.create_function(parse_int)?;
+    assert_eq!(parse_int.call::<i64>("42")?, 42);
+    let err = parse_int.call::<i64>("4x2").unwrap_err();
+    assert!(err.to_string().contains("invalid digit"));
+
+    let describe_len = lua.create_function(describe_len)?;
+    assert_eq!(describe_len.call::<String>("")?, "empty");
+    assert_eq!(describe_len.call::<String>("four")?, "4 chars");
+    let parse_twice = lua.create_function(parse_twice)?;
+    assert_eq!(parse_twice.call::<i64>("21")?, 42);
+    assert!(parse_twice.call::<i64>("x").is_err());
+
+    assert_eq!(double(4), 8);
+    let double = lua.create_function(double_lua)?;
+    assert_eq!(double.call::<i64>(21)?, 42);
+
+    let to_percent = lua.create_function(to_percent_lua)?;
+    assert_eq!(to_percent.call::<u8>(0.5)?, 50);
+    let err = to_percent.call::<u8>(2.0).unwrap_err();
+    assert!(err.to_string().contains("value is out of range"));
+
+    // Errors are converted with `Into<mlua::Error>` when they can be, even if they're standard errors
+    let fail = lua.create_function(fail)?;
+    match fail.call::<u32>("boom").unwrap_err() {
+        mlua::Error::CallbackError { cause, .. } => {
+            assert!(
+                matches!(cause.as_ref(), mlua::Error::RuntimeError(message) if message == "boom")
+            )
+        }
+        err => panic!("expected a callback error, got {err:?}"),
+    }
+    let checked_add = lua.create_function(checked_add)?;
+    assert_eq!(checked_add.call::<u8>((1, 2))?, 3);
+    match checked_add.call::<u8>((255, 1)).unwrap_err() {
+        mlua::Error::CallbackError { cause, .. } => assert!(matches!(
+            cause.as_ref(),
+            mlua::Error::RuntimeError(message) if message == "the sum doesn't fit"
+        )),
+        err => panic!("expected a callback error, got {err:?}"),
+    }
+
+    #[cfg(feature = "anyhow")]
+    {
+        let checked_div = lua.create_function(checked_div_lua)?;
+        assert_eq!(checked_div.call::<i64>((9, 3))?, 3);
+        let err = checked_div.call::<i64>((1, 0)).unwrap_err();
+        assert!(err.to_string().contains("division by zero"));
+    }
+
     Ok(())
 }
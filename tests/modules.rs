@@ -38,7 +38,7 @@ mod inner {
     /// ```
     #[mlua_bindgen]
     pub fn lua_mul(_: &mlua::Lua, val1: f32, val2: f32) -> f32 {
-        Ok(mul(val1, val2))
+        mul(val1, val2)
     }
 
     /// The same thing applies here. LuaSecret in the module will be referenced as "Secret"
@@ -46,12 +46,12 @@ mod inner {
     impl LuaSecret {
         #[func]
         pub fn new(_: &Lua) -> Self {
-            Ok(Self(crate::Secret { value: 44 }))
+            Self(crate::Secret { value: 44 })
         }
 
         #[method]
         pub fn value(_: &Lua, this: &Self) -> u32 {
-            Ok(this.0.value)
+            this.0.value
         }
     }
 }
@@ -83,40 +83,36 @@ mod math {
     impl Vector {
         #[func]
         fn new(_: _, x: f32, y: f32) -> Self {
-            Ok(Self::new(x, y))
+            Self::new(x, y)
         }
 
         //
         #[method]
         fn add(_: _, this: &Self, with: Self) -> Self {
-            let res = Self {
+            Self {
                 x: this.x + with.x,
                 y: this.y + with.y,
-            };
-
-            Ok(res)
+            }
         }
 
         #[get]
         fn x(_: _, this: &Self) -> f32 {
-            Ok(this.x)
+            this.x
         }
 
         #[get]
         fn y(_: _, this: &Self) -> f32 {
-            Ok(this.y)
+            this.y
         }
 
         #[set]
         fn x(_: _, this: &mut Self, to: f32) {
             this.x = to;
-            Ok(())
         }
 
         #[set]
         fn y(_: _, this: &mut Self, to: f32) {
             this.y = to;
-            Ok(())
         }
     }
 
@@ -124,8 +120,28 @@ mod math {
     #[mlua_bindgen]
     pub fn add_to_counter(_: &mlua::Lua, what: u32) {
         COUNTER.fetch_add(what, Ordering::Relaxed);
+    }
+
+    /// Kept functions are registered through their adapters
+    #[mlua_bindgen(keep)]
+    pub fn get_counter() -> mlua::Result<u32> {
+        Ok(COUNTER.load(Ordering::Relaxed))
+    }
 
-        Ok(())
+    /// Every instance of a generic function is registered under its own name
+    #[mlua_bindgen(instantiate(int_max(T = i64), str_max(T = String)))]
+    pub fn max<T: PartialOrd>(a: T, b: T) -> T {
+        if a > b {
+            a
+        } else {
+            b
+        }
+    }
+
+    /// An unnamed instance keeps the name of the function
+    #[mlua_bindgen(keep, instantiate(T = mlua::Value))]
+    pub fn lua_echo<T>(val: T) -> T {
+        val
     }
 }
 
@@ -142,8 +158,15 @@ fn modules() -> mlua::Result<()> {
         local secret = math.inner.Secret.new()
         assert(secret:value() == 44)
 
+        -- Generic function instances
+        assert(math.int_max(3, 7) == 7)
+        assert(math.str_max('a', 'b') == 'b')
+        assert(math.echo(math) == math)
+        assert(math.max == nil)
+
         -- Add value to the counter
         math.add_to_counter(38)
+        assert(math.get_counter() == 43)
 
         -- Using inner modules with unprefixed items!
         local vec1_x = math.inner.mul(2, 5)
@@ -161,3 +184,371 @@ fn modules() -> mlua::Result<()> {
 
     Ok(())
 }
+
+#[mlua_bindgen(rename_all = "camelCase")]
+mod world {
+    use mlua_bindgen::mlua_bindgen;
+
+    pub struct LuaEntity {
+        max_health: u32,
+    }
+
+    #[mlua_bindgen(rename_all = "camelCase", include = [movement])]
+    impl LuaEntity {
+        #[func]
+        fn spawn_with(_: _, max_health: u32) -> Self {
+            Self { max_health }
+        }
+
+        #[get]
+        fn max_health(_: _, this: &Self) -> u32 {
+            this.max_health
+        }
+
+        #[set]
+        #[mlua_bindgen(name = "maxHealth")]
+        fn set_max_health(_: _, this: &mut Self, to: u32) {
+            this.max_health = to;
+        }
+
+        #[method]
+        #[mlua_bindgen(preserve)]
+        fn is_alive(_: _, this: &Self) -> bool {
+            this.max_health > 0
+        }
+    }
+
+    /// Parts aren't registered by the module, since their main impl block does it already
+    #[mlua_bindgen(partial = movement, rename_all = "camelCase")]
+    impl LuaEntity {
+        #[method]
+        fn move_speed(&self) -> f32 {
+            self.max_health as f32 / 10.0
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    #[mlua_bindgen(name = "Kind")]
+    pub enum EntityKind {
+        Player,
+        Monster,
+    }
+
+    #[mlua_bindgen]
+    pub fn get_player_name() -> String {
+        "Steve".to_owned()
+    }
+
+    #[mlua_bindgen(name = "teleport")]
+    pub fn lua_move_to(x: f32) -> f32 {
+        x
+    }
+
+    #[mlua_bindgen(preserve)]
+    pub fn lua_raw_query() -> bool {
+        true
+    }
+}
+
+#[test]
+fn renamed_items() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("world", world_module(&lua)?)?;
+
+    let kind = lua
+        .load(
+            "
+        assert(world.getPlayerName() == 'Steve')
+        assert(world.teleport(4) == 4)
+        assert(world.lua_raw_query())
+        assert(world.get_player_name == nil)
+
+        -- Types aren't renamed by the module convention
+        local entity = world.Entity.spawnWith(20)
+        entity.maxHealth = entity.maxHealth + 5
+        assert(entity.maxHealth == 25)
+        assert(entity:is_alive() and entity:moveSpeed() == 2.5)
+        return world.Kind.Monster
+    ",
+        )
+        .eval::<usize>()?;
+    assert_eq!(
+        world::EntityKind::from_usize(kind),
+        Some(world::EntityKind::Monster)
+    );
+
+    Ok(())
+}
+
+#[mlua_bindgen]
+mod assets {
+    use mlua::{FromLua, IntoLua};
+    use mlua_bindgen::mlua_bindgen;
+
+    pub struct Handle<T> {
+        value: T,
+        loads: u32,
+    }
+
+    /// Defined in the module, so the instance's type argument is only known here
+    #[derive(Clone, FromLua)]
+    pub struct Mesh {
+        vertices: u32,
+    }
+
+    impl mlua::UserData for Mesh {
+        fn add_fields<F: mlua::UserDataFields<Self>>(fields: &mut F) {
+            fields.add_field_method_get("vertices", |_, this| Ok(this.vertices));
+        }
+    }
+
+    #[mlua_bindgen]
+    pub fn mesh(vertices: u32) -> Mesh {
+        Mesh { vertices }
+    }
+
+    /// Generic impls are registered by their instances
+    #[mlua_bindgen(
+        instantiate(TextHandle(T = String), NumberHandle(T = f64), MeshHandle(T = Mesh)),
+        include = [loading]
+    )]
+    impl<T: FromLua + IntoLua + Clone> Handle<T> {
+        #[func]
+        fn new(value: T) -> Self {
+            Self { value, loads: 0 }
+        }
+
+        #[get]
+        fn value(&self) -> T {
+            self.value.clone()
+        }
+    }
+
+    #[mlua_bindgen(partial = loading)]
+    impl<T: FromLua + IntoLua + Clone> Handle<T> {
+        #[method_mut]
+        fn load(&mut self) -> T {
+            self.loads += 1;
+            self.value.clone()
+        }
+
+        #[get]
+        fn loads(&self) -> u32 {
+            self.loads
+        }
+    }
+}
+
+#[test]
+fn generic_impls() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("assets", assets_module(&lua)?)?;
+    // Generic impls work for any type, even without instances
+    lua.globals()
+        .set("FlagHandle", assets::Handle::<bool>::as_table(&lua)?)?;
+
+    lua.load(
+        "
+        local text = assets.TextHandle.new('hello')
+        assert(text.value == 'hello' and text:load() == 'hello' and text.loads == 1)
+
+        local number = assets.NumberHandle.new(2.5)
+        assert(number.value == 2.5 and number.loads == 0)
+        assert(not pcall(assets.NumberHandle.new, 'not a number'))
+
+        local mesh = assets.MeshHandle.new(assets.mesh(8))
+        assert(mesh.value.vertices == 8)
+
+        assert(FlagHandle.new(true):load() == true)
+    ",
+    )
+    .exec()
+}
+
+#[mlua_bindgen]
+mod shapes {
+    use mlua_bindgen::mlua_bindgen;
+
+    pub struct Circle {
+        radius: f64,
+    }
+
+    /// Exported as a userdata proxy, so the class and instance items live on the same type
+    #[mlua_bindgen(include = [scaling])]
+    impl Circle {
+        #[func]
+        fn new(radius: f64) -> Self {
+            Self { radius }
+        }
+
+        #[constant]
+        const UNIT: Self = Self { radius: 1.0 };
+
+        #[constructor]
+        fn create(radius: f64) -> Self {
+            Self { radius }
+        }
+
+        #[get]
+        fn radius(&self) -> f64 {
+            self.radius
+        }
+    }
+
+    #[mlua_bindgen(partial = scaling)]
+    impl Circle {
+        #[func]
+        fn scaled(circle: Self, by: f64) -> Self {
+            Self {
+                radius: circle.radius * by,
+            }
+        }
+    }
+
+    impl Clone for Circle {
+        fn clone(&self) -> Self {
+            Self {
+                radius: self.radius,
+            }
+        }
+    }
+
+    impl mlua::FromLua for Circle {
+        fn from_lua(value: mlua::Value, _: &mlua::Lua) -> mlua::Result<Self> {
+            match value {
+                mlua::Value::UserData(ud) => Ok(ud.borrow::<Self>()?.clone()),
+                _ => Err(mlua::Error::runtime("expected a circle")),
+            }
+        }
+    }
+
+    pub struct Square {
+        side: f64,
+    }
+
+    /// Kept as a plain table
+    #[mlua_bindgen(as_table)]
+    impl Square {
+        #[func]
+        fn new(side: f64) -> Self {
+            Self { side }
+        }
+
+        #[get]
+        fn side(&self) -> f64 {
+            self.side
+        }
+    }
+}
+
+#[test]
+fn proxies() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("shapes", shapes_module(&lua)?)?;
+
+    lua.load(
+        "
+        local Circle = shapes.Circle
+        assert(type(Circle) == 'userdata')
+        assert(Circle.new(2).radius == 2 and Circle(3).radius == 3)
+        assert(Circle.UNIT.radius == 1 and Circle.scaled(Circle.UNIT, 4).radius == 4)
+
+        -- The class items are available on the instances as well, but only the class can be called
+        local circle = Circle.new(5)
+        assert(circle.new(1).radius == 1 and circle.UNIT.radius == 1)
+        local ok, err = pcall(circle, 1)
+        assert(not ok and string.find(tostring(err), [[attempt to call an instance of 'Circle']], 1, true))
+
+        -- Unlike tables, proxies can't be changed by scripts
+        assert(not pcall(function() Circle.new = nil end))
+
+        assert(type(shapes.Square) == 'table' and shapes.Square.new(2).side == 2)
+        assert(not pcall(function() return shapes.Square.new(2).new end))
+    ",
+    )
+    .exec()
+}
+
+#[mlua_bindgen]
+mod time {
+    use mlua::UserDataRef;
+    use mlua_bindgen::mlua_bindgen;
+    use std::time::Duration;
+
+    /// Only holds the bindings of `Duration`, which can't implement `UserData` outside of std
+    pub struct DurationDef;
+
+    #[mlua_bindgen(remote = Duration)]
+    impl DurationDef {
+        #[func]
+        fn from_secs(secs: f64) -> Duration {
+            Duration::from_secs_f64(secs)
+        }
+
+        /// Returns the remote type through another path
+        #[func]
+        fn from_mins(mins: u64) -> std::time::Duration {
+            Duration::from_secs(mins * 60)
+        }
+
+        #[constructor]
+        fn from_millis(millis: u64) -> Duration {
+            Duration::from_millis(millis)
+        }
+
+        #[constant]
+        const ZERO: Duration = Duration::ZERO;
+
+        #[get]
+        fn secs(&self) -> f64 {
+            self.as_secs_f64()
+        }
+
+        #[method_mut]
+        fn double(&mut self) {
+            *self *= 2;
+        }
+
+        /// `Self` is the remote type as well
+        #[method]
+        fn halved(&self) -> Self {
+            *self / 2
+        }
+
+        #[meta]
+        fn __add(a: UserDataRef<Duration>, b: UserDataRef<Duration>) -> Duration {
+            *a + *b
+        }
+
+        #[meta]
+        fn __eq(&self, other: UserDataRef<Duration>) -> bool {
+            *self == *other
+        }
+    }
+}
+
+#[test]
+fn remote_types() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("time", time_module(&lua)?)?;
+    lua.globals().set(
+        "three",
+        lua.create_any_userdata(std::time::Duration::from_secs(3))?,
+    )?;
+
+    lua.load(
+        "
+        local Duration = time.Duration
+        assert(type(Duration) == 'table')
+        assert(Duration.from_secs(1.5).secs == 1.5 and Duration(500).secs == 0.5)
+        assert(Duration.ZERO.secs == 0 and three.secs == 3)
+
+        local sum = Duration.from_secs(1.5) + Duration(500)
+        assert(sum == Duration.from_secs(2))
+        sum:double()
+        assert(sum.secs == 4)
+        assert(Duration.from_mins(2).secs == 120 and sum:halved() == Duration.from_secs(2))
+    ",
+    )
+    .exec()
+}
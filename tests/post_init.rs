@@ -17,12 +17,12 @@ mod module {
 
     #[mlua_bindgen]
     pub fn my_function(_: &mlua::Lua) -> u32 {
-        Ok(5)
+        5
     }
 
     #[mlua_bindgen]
     pub fn remove_me(_: &mlua::Lua) -> u32 {
-        Ok(222)
+        222
     }
 }
 
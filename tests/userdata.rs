@@ -10,37 +10,498 @@ pub struct ResId {
 impl ResId {
     #[func]
     fn new(_: &mlua::Lua, with: u64) -> Self {
-        Ok(Self { id: with })
+        Self { id: with }
     }
 
     #[get]
     fn id(_: &mlua::Lua, this: &Self) -> u64 {
-        Ok(this.id)
+        this.id
     }
 
     #[method]
-    fn do_something(_: &mlua::Lua, _this: &Self) {
-        Ok(())
+    fn do_something(_: &mlua::Lua, _this: &Self) {}
+
+    /// Both the Lua state and `this` can be omitted
+    #[method]
+    fn kind() -> String {
+        "resource".to_owned()
     }
 
-    #[method_mut]
-    fn do_something_mut(_: &mlua::Lua, _this: &mut Self) {
-        Ok(())
+    #[method]
+    fn is_same(this: &Self, id: u64) -> bool {
+        this.id == id
     }
 
+    #[method]
+    fn offset(this: &Self, #[default(1)] by: u64) -> u64 {
+        this.id + by
+    }
+
+    #[func]
+    #[mlua_bindgen(table_args)]
+    fn from_parts(high: u64, low: u64) -> Self {
+        Self {
+            id: (high << 32) | low,
+        }
+    }
+
+    #[method_mut]
+    fn do_something_mut(_: &mlua::Lua, _this: &mut Self) {}
+
     #[meta]
     fn __add(_: &mlua::Lua, a: Self, b: Self) -> Self {
-        Ok(Self { id: a.id + b.id })
+        Self { id: a.id + b.id }
     }
 
     #[meta]
     fn __eq(_: &mlua::Lua, this: Self, val: Self) -> bool {
-        Ok(this.id == val.id)
+        this.id == val.id
     }
 
     #[meta]
     fn __tostring(_: &mlua::Lua, this: Self) -> String {
-        Ok(format!("<ResId {}>", this.id))
+        format!("<ResId {}>", this.id)
+    }
+}
+
+pub struct Counter {
+    count: u32,
+}
+
+/// Kept impl functions stay callable from Rust, and don't have to return `mlua::Result`
+#[mlua_bindgen(keep)]
+impl Counter {
+    #[func]
+    fn new(#[default(0)] count: u32) -> Self {
+        Self { count }
+    }
+
+    #[func]
+    #[mlua_bindgen(table_args)]
+    fn starting_at(count: u32) -> Self {
+        Self { count }
+    }
+
+    #[get]
+    #[lua_type("integer")]
+    fn count(this: &Self) -> u32 {
+        this.count
+    }
+
+    #[method_mut]
+    fn increment(this: &mut Self) -> u32 {
+        this.count += 1;
+        this.count
+    }
+
+    #[method_mut]
+    fn add(this: &mut Self, by: String) -> Result<u32, std::num::ParseIntError> {
+        this.count += by.parse::<u32>()?;
+        Ok(this.count)
+    }
+
+    /// Kept methods keep their receivers as well
+    #[method]
+    fn doubled(&self) -> u32 {
+        self.count * 2
+    }
+
+    #[method_mut]
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+
+    #[static_field]
+    fn limit() -> u32 {
+        100
+    }
+
+    #[constructor]
+    fn with_count(count: u32) -> Self {
+        Self { count }
+    }
+
+    #[get_function]
+    fn is_zero(ud: mlua::AnyUserData) -> mlua::Result<bool> {
+        Ok(ud.borrow::<Self>()?.count == 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct Point {
+    x: f64,
+    y: f64,
+}
+
+/// Methods can take real receivers, which are passed to the body as `this`. Exported as a table, so the class
+/// items aren't registered on the instances
+#[mlua_bindgen(as_table)]
+impl Point {
+    #[func]
+    fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// `Point(1, 2)` or `Point{ x = 1, y = 2 }`
+    #[constructor(table)]
+    fn create(x: f64, #[default(0.0)] y: f64) -> Self {
+        Self { x, y }
+    }
+
+    #[get]
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    #[set]
+    fn x(&mut self, to: f64) {
+        self.x = to;
+    }
+
+    #[method]
+    fn length(&self, lua: &mlua::Lua) -> f64 {
+        let _ = lua.globals();
+        self.dot(self).sqrt()
+    }
+
+    /// Items without Lua attributes stay in Rust
+    const ORIGIN: Point = Point { x: 0.0, y: 0.0 };
+
+    /// Exported constants stay in Rust as well
+    #[constant]
+    #[mlua_bindgen(name = "UNIT_X")]
+    const UNIT: Point = Point { x: 1.0, y: 0.0 };
+
+    #[constant(field)]
+    const DIMENSIONS: u32 = 2;
+
+    pub fn dot(&self, other: &Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    #[method_mut]
+    fn scale(&mut self, by: f64) {
+        self.x *= by;
+        self.y *= by;
+    }
+
+    /// By-value receivers take a clone of the userdata
+    #[method]
+    fn moved(mut self, dx: f64) -> Self {
+        self.x += dx;
+        self
+    }
+
+    #[meta]
+    fn __tostring(&self) -> String {
+        format!("({}, {})", self.x, self.y)
+    }
+
+    #[meta]
+    fn __unm(&mut self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+/// Only held by players, so it's never converted to Lua
+pub struct Inventory(Vec<String>);
+
+/// Struct fields are exposed with `#[lua(...)]`, while the impl block adds the rest
+#[mlua_bindgen(rename_all = "camelCase")]
+pub struct Player {
+    #[lua(get, set)]
+    name: String,
+    #[lua(get)]
+    level: u32,
+    #[lua(set, get, name = "hp")]
+    health: f32,
+    #[lua(skip)]
+    secret_key: u64,
+    #[lua(get)]
+    max_level: u32,
+    inventory: Inventory,
+}
+
+#[mlua_bindgen(include = [combat, debug])]
+impl Player {
+    #[func]
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            level: 1,
+            health: 100.0,
+            secret_key: 42,
+            max_level: 10,
+            inventory: Inventory(Vec::new()),
+        }
+    }
+
+    #[method_mut]
+    fn level_up(&mut self) {
+        self.level += 1;
+    }
+}
+
+/// A part of the `Player` API, registered by its main impl block
+#[mlua_bindgen(partial = combat)]
+impl Player {
+    #[get]
+    fn is_alive(&self) -> bool {
+        self.health > 0.0
+    }
+
+    #[method_mut]
+    fn damage(&mut self, by: f32) -> f32 {
+        self.health = (self.health - by).max(0.0);
+        self.health
+    }
+
+    #[func]
+    fn max_health() -> f32 {
+        100.0
+    }
+
+    /// Nested items keep their own `self`
+    #[get]
+    fn title(&self) -> String {
+        struct Title<'a>(&'a str);
+
+        impl Title<'_> {
+            fn knighted(&self) -> String {
+                format!("Sir {}", self.0)
+            }
+        }
+
+        Title(&self.name).knighted()
+    }
+
+    #[meta]
+    fn __tostring(&self) -> String {
+        format!("Player({})", self.name)
+    }
+
+    const MAX_HEALTH: f32 = 100.0;
+}
+
+/// Disabled parts are skipped
+#[mlua_bindgen(partial = debug)]
+#[cfg(any())]
+impl Player {
+    #[method]
+    fn dump(&self) -> String {
+        Ok(String::new())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, FromLua)]
+pub struct Meters(f64);
+
+impl std::ops::Add for Meters {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl std::ops::Sub for Meters {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl std::ops::Neg for Meters {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl std::fmt::Display for Meters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}m", self.0)
+    }
+}
+
+/// Metamethods derived from the traits above
+#[mlua_bindgen(meta(add, sub, unm, eq, lt, le, tostring, len))]
+impl Meters {
+    #[func]
+    fn new(value: f64) -> Self {
+        Self(value)
+    }
+
+    /// Whole meters
+    fn len(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+pub struct Tile {
+    kind: String,
+    solid: bool,
+}
+
+/// Every other registration kind of mlua
+#[mlua_bindgen]
+impl Tile {
+    #[func]
+    fn new(kind: String) -> Self {
+        Self { kind, solid: false }
+    }
+
+    /// Works both as `tile:describe(...)` and `tile.describe(tile, ...)`
+    #[function]
+    fn describe(ud: mlua::AnyUserData, prefix: String) -> mlua::Result<String> {
+        Ok(format!("{prefix}{}", ud.borrow::<Self>()?.kind))
+    }
+
+    #[function_mut]
+    fn toggle(ud: mlua::AnyUserData) -> mlua::Result<()> {
+        let mut tile = ud.borrow_mut::<Self>()?;
+        tile.solid = !tile.solid;
+        Ok(())
+    }
+
+    #[meta_method]
+    fn __len(this: &Self) -> usize {
+        this.kind.len()
+    }
+
+    #[meta_method_mut]
+    fn __call(this: &mut Self, solid: bool) {
+        this.solid = solid;
+    }
+
+    #[get_function]
+    fn kind(ud: mlua::AnyUserData) -> mlua::Result<String> {
+        Ok(ud.borrow::<Self>()?.kind.clone())
+    }
+
+    #[set_function]
+    #[mlua_bindgen(name = "kind")]
+    fn set_kind(ud: mlua::AnyUserData, kind: String) -> mlua::Result<()> {
+        ud.borrow_mut::<Self>()?.kind = kind;
+        Ok(())
+    }
+
+    #[get]
+    fn solid(&self) -> bool {
+        self.solid
+    }
+
+    #[static_field]
+    fn size(lua: &mlua::Lua) -> mlua::Result<mlua::Table> {
+        lua.create_sequence_from([16, 16])
+    }
+
+    #[meta_field]
+    fn __name() -> &'static str {
+        "Tile"
+    }
+}
+
+pub struct Node {
+    name: String,
+}
+
+#[mlua_bindgen]
+impl Node {
+    #[get]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[set]
+    #[mlua_bindgen(name = "name")]
+    fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    #[method]
+    fn path(&self, parent: String) -> String {
+        format!("{parent}/{}", self.name)
+    }
+}
+
+pub struct Sprite {
+    node: Node,
+    frame: u32,
+}
+
+impl Sprite {
+    fn node(&self) -> &Node {
+        &self.node
+    }
+
+    fn node_mut(&mut self) -> &mut Node {
+        &mut self.node
+    }
+}
+
+/// Node's fields and methods are looked up on `node`
+#[mlua_bindgen(extends = Node, via = Sprite::node, via_mut = Sprite::node_mut)]
+impl Sprite {
+    #[func]
+    fn new(name: String) -> Self {
+        Self {
+            node: Node { name },
+            frame: 0,
+        }
+    }
+
+    #[get]
+    fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    #[method_mut]
+    fn advance(&mut self) {
+        self.frame += 1;
+    }
+}
+
+pub struct AnimatedSprite {
+    sprite: Sprite,
+    speed: f32,
+}
+
+impl AnimatedSprite {
+    fn sprite(&self) -> &Sprite {
+        &self.sprite
+    }
+
+    fn sprite_mut(&mut self) -> &mut Sprite {
+        &mut self.sprite
+    }
+}
+
+#[mlua_bindgen(
+    extends = Sprite,
+    via = AnimatedSprite::sprite,
+    via_mut = AnimatedSprite::sprite_mut
+)]
+impl AnimatedSprite {
+    #[func]
+    fn new(name: String, speed: f32) -> Self {
+        Self {
+            sprite: Sprite {
+                node: Node { name },
+                frame: 0,
+            },
+            speed,
+        }
+    }
+
+    #[get]
+    fn speed(&self) -> f32 {
+        self.speed
     }
 }
 
@@ -56,6 +517,17 @@ fn userdata() -> mlua::Result<()> {
         local res_id = ResId.new(127) 
         assert(res_id.id == 127)
 
+        -- Methods without the Lua and self arguments
+        assert(res_id:kind() == 'resource')
+        assert(res_id:is_same(127))
+
+        -- Arguments with default values
+        assert(res_id:offset() == 128)
+        assert(res_id:offset(3) == 130)
+
+        -- Functions with table arguments
+        assert(ResId.from_parts{ high = 1, low = 2 }.id == 4294967298)
+
         -- Check if the __tostring works correctly
         assert(tostring(res_id) == '<ResId 127>')
 
@@ -76,3 +548,298 @@ fn userdata() -> mlua::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn kept_userdata() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+
+    let mut counter = Counter::new(0);
+    assert_eq!(Counter::increment(&mut counter), 1);
+    assert_eq!(Counter::count(&counter), 1);
+    assert_eq!(Counter::starting_at(3).count, 3);
+    assert_eq!(Counter::limit(), 100);
+
+    lua.globals().set("Counter", Counter::as_table(&lua)?)?;
+    lua.load(
+        "
+        local counter = Counter.new()
+        assert(counter:increment() == 1)
+        assert(counter:add('5') == 6)
+        assert(counter.count == 6)
+        assert(not pcall(counter.add, counter, 'five'))
+        assert(Counter.new(10).count == 10)
+        assert(Counter.starting_at{ count = 3 }.count == 3)
+        assert(counter.limit == 100 and Counter.new().is_zero and not counter.is_zero)
+    ",
+    )
+    .exec()?;
+
+    Ok(())
+}
+
+#[test]
+fn receivers() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Point", Point::as_table(&lua)?)?;
+    lua.globals().set("Counter", Counter::as_table(&lua)?)?;
+
+    assert_eq!(Point::ORIGIN.dot(&Point { x: 1.0, y: 2.0 }), 0.0);
+    let mut counter = Counter::new(4);
+    assert_eq!(counter.doubled(), 8);
+    counter.reset();
+    assert_eq!(counter.count, 0);
+
+    lua.load(
+        "
+        local point = Point.new(3, 4)
+        assert(point:length() == 5)
+        point:scale(2)
+        assert(tostring(point) == '(6, 8)')
+
+        -- The original userdata isn't affected by by-value receivers
+        local moved = point:moved(1)
+        assert(moved.x == 7 and point.x == 6)
+
+        point.x = 1
+        assert(tostring(-point) == '(-1, -8)')
+        assert(not pcall(point.scale, point, 'twice'))
+        assert(not pcall(function() return point:dot(point) end))
+
+        local counter = Counter.new(5)
+        assert(counter:doubled() == 10)
+        counter:reset()
+        assert(counter.count == 0)
+    ",
+    )
+    .exec()
+}
+
+#[test]
+fn constructors() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Point", Point::as_table(&lua)?)?;
+    lua.globals().set("Counter", Counter::as_table(&lua)?)?;
+    assert_eq!(Counter::with_count(2).count, 2);
+
+    lua.load(
+        "
+        assert(tostring(Point(3, 4)) == '(3, 4)' and tostring(Point(3)) == '(3, 0)')
+        assert(tostring(Point{ x = 1, y = 2 }) == '(1, 2)' and tostring(Point{ x = 5 }) == '(5, 0)')
+        assert(tostring(Point.new(1, 1)) == '(1, 1)')
+        assert(Counter(7).count == 7)
+    ",
+    )
+    .exec()?;
+
+    let error = |code: &str| lua.load(code).exec().unwrap_err().to_string();
+    assert!(error("Point('a')").contains("bad argument `x` to `Point"));
+    assert!(error("Point{ y = 1 }").contains("error converting Lua nil"));
+    Ok(())
+}
+
+#[test]
+fn constants() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Point", Point::as_table(&lua)?)?;
+    assert_eq!(Point::UNIT.x + Point::DIMENSIONS as f64, 3.0);
+
+    lua.load(
+        "
+        assert(tostring(Point.UNIT_X) == '(1, 0)' and Point.UNIT == nil)
+        assert(Point.DIMENSIONS == 2 and Point.new(1, 2).DIMENSIONS == 2)
+        assert(Point.new(1, 2).UNIT_X == nil)
+    ",
+    )
+    .exec()
+}
+
+#[test]
+fn struct_fields() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Player", Player::as_table(&lua)?)?;
+    let player = Player {
+        name: "Steve".to_owned(),
+        level: 1,
+        health: 100.0,
+        secret_key: 42,
+        max_level: 10,
+        inventory: Inventory(vec!["sword".to_owned()]),
+    };
+    lua.globals().set("player", player)?;
+
+    lua.load(
+        "
+        player.name = 'Alex'
+        player.hp = player.hp - 25
+        player:level_up()
+        assert(player.name == 'Alex' and player.level == 2 and player.hp == 75)
+        assert(player.maxLevel == 10)
+        assert(player.secretKey == nil and player.secret_key == nil)
+
+        -- Fields without `#[lua]` aren't exposed either
+        assert(player.inventory == nil)
+
+        -- Fields without `set` are read-only
+        assert(not pcall(function() player.level = 5 end))
+
+        local other = Player.new('Steve')
+        assert(other.name == 'Steve' and other.level == 1)
+    ",
+    )
+    .exec()?;
+
+    let player = lua.globals().get::<mlua::AnyUserData>("player")?;
+    player.borrow_scoped::<Player, _>(|player| {
+        assert_eq!((player.name.as_str(), player.secret_key), ("Alex", 42));
+        assert_eq!(player.inventory.0, ["sword"]);
+    })?;
+
+    let error = lua
+        .load("player.hp = 'full'")
+        .exec()
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("bad argument `health` to `Player.hp"));
+    Ok(())
+}
+
+#[test]
+fn partial_impls() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Player", Player::as_table(&lua)?)?;
+    assert_eq!(Player::MAX_HEALTH, 100.0);
+
+    lua.load(
+        "
+        local player = Player.new('Steve')
+        assert(Player.max_health() == 100)
+        assert(player:damage(30) == 70 and player.is_alive)
+        player:damage(100)
+        assert(not player.is_alive and tostring(player) == 'Player(Steve)')
+        assert(player.title == 'Sir Steve')
+        player:level_up()
+        assert(player.level == 2)
+        assert(player.dump == nil)
+    ",
+    )
+    .exec()
+}
+
+#[test]
+fn derived_meta() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Meters", Meters::as_table(&lua)?)?;
+    assert_eq!(Meters(2.5).len(), 2);
+
+    lua.load(
+        "
+        local a, b = Meters.new(1.5), Meters.new(2)
+        assert(a + b == Meters.new(3.5) and b - a == Meters.new(0.5))
+        assert(-a == Meters.new(-1.5) and a ~= b)
+        assert(a < b and a <= a and not (b < a))
+        assert(tostring(a + b) == '3.5m' and #Meters.new(7.9) == 7)
+    ",
+    )
+    .exec()?;
+
+    let error = lua
+        .load("return Meters.new(1) + 5")
+        .exec()
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("bad argument `b` to `Meters.__add"));
+    Ok(())
+}
+
+#[test]
+fn registration_kinds() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Tile", Tile::as_table(&lua)?)?;
+
+    lua.load(
+        "
+        local tile = Tile.new('grass')
+        assert(tile:describe('a ') == 'a grass' and tile.describe(tile, '') == 'grass')
+        tile:toggle()
+        assert(tile.solid and #tile == 5)
+        tile(false)
+        assert(not tile.solid)
+
+        tile.kind = 'stone'
+        assert(tile.kind == 'stone')
+        assert(tile.size[1] == 16 and Tile.new('sand').size[2] == 16)
+        assert(tostring(tile):find('^Tile'))
+    ",
+    )
+    .exec()?;
+    Ok(())
+}
+
+#[test]
+fn userdata_argument_errors() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("ResId", ResId::as_table(&lua)?)?;
+
+    let error = |code: &str| lua.load(code).exec().unwrap_err().to_string();
+    assert!(error("ResId.new('x')").contains("bad argument `with` to `ResId.new"));
+    assert!(error("ResId.new(1):is_same(true)").contains("bad argument `id` to `ResId:is_same"));
+
+    // Lua counts `self` as the first method argument
+    let error = lua.load("ResId.new(1):is_same(true)").exec().unwrap_err();
+    match error {
+        mlua::Error::CallbackError { cause, .. } => {
+            assert!(matches!(*cause, mlua::Error::BadArgument { pos: 2, .. }))
+        }
+        err => panic!("expected a callback error, got {err:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn extended_types() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    lua.globals().set("Sprite", Sprite::as_table(&lua)?)?;
+    lua.globals()
+        .set("AnimatedSprite", AnimatedSprite::as_table(&lua)?)?;
+
+    lua.load(
+        "
+        local sprite = Sprite.new('player')
+        assert(sprite.name == 'player' and sprite:path('root') == 'root/player')
+        sprite.name = 'enemy'
+        sprite:advance()
+        assert(sprite.name == 'enemy' and sprite.frame == 1)
+
+        -- Lookups go through the whole chain of bases
+        local animated = AnimatedSprite.new('coin', 2)
+        animated:advance()
+        animated.name = 'gem'
+        assert(animated.speed == 2 and animated.frame == 1)
+        assert(animated:path('items') == 'items/gem')
+        assert(animated.missing == nil)
+
+        -- Base methods are wrapped once
+        assert(animated.path == animated.path)
+    ",
+    )
+    .exec()?;
+
+    // The class proxy shares the metatable of the type, but has no base
+    lua.globals()
+        .set("SpriteProxy", lua.create_proxy::<Sprite>()?)?;
+    lua.load(
+        "
+        assert(Sprite.new('player').nope == nil)
+        assert(SpriteProxy.nope == nil)
+    ",
+    )
+    .exec()?;
+    assert!(lua.load("SpriteProxy.nope = 1").exec().is_err());
+
+    assert!(lua
+        .load("AnimatedSprite.new('coin', 1).frame = 2")
+        .exec()
+        .is_err());
+    Ok(())
+}
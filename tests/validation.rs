@@ -0,0 +1,101 @@
+use macros::mlua_bindgen;
+
+fn no_spaces(name: &str) -> Result<(), String> {
+    match name.contains(' ') {
+        true => Err("can't contain spaces".to_owned()),
+        false => Ok(()),
+    }
+}
+
+#[mlua_bindgen]
+pub fn set_volume(#[range(0..=100)] volume: u8) -> u8 {
+    volume
+}
+
+#[mlua_bindgen]
+pub fn rename(
+    #[non_empty]
+    #[len(max = 8)]
+    #[validate(no_spaces)]
+    name: String,
+    #[len(min = 2)] alias: Option<String>,
+) -> String {
+    alias.unwrap_or(name)
+}
+
+#[mlua_bindgen(keep, table_args)]
+pub fn resize(
+    #[range(1.0..)] width: f64,
+    #[default(1.0)]
+    #[range(0.5..=2.0)]
+    scale: f64,
+) -> f64 {
+    width * scale
+}
+
+pub struct Slider {
+    value: f32,
+}
+
+#[mlua_bindgen]
+impl Slider {
+    #[func]
+    fn new() -> Self {
+        Self { value: 0.0 }
+    }
+
+    #[method_mut]
+    fn set(this: &mut Self, #[range(0.0..=1.0)] value: f32) {
+        this.value = value;
+    }
+}
+
+fn error_message(lua: &mlua::Lua, code: &str) -> String {
+    lua.load(code).exec().unwrap_err().to_string()
+}
+
+#[test]
+fn checks() -> mlua::Result<()> {
+    let lua = mlua::Lua::new();
+    let globals = lua.globals();
+    globals.set("set_volume", lua.create_function(set_volume)?)?;
+    globals.set("rename", lua.create_function(rename)?)?;
+    globals.set("resize", lua.create_function(resize_lua)?)?;
+    globals.set("Slider", Slider::as_table(&lua)?)?;
+
+    lua.load(
+        "
+        assert(set_volume(100) == 100)
+        assert(rename('orc') == 'orc')
+        assert(rename('orc', 'og') == 'og')
+        assert(resize{ width = 2 } == 2)
+        assert(resize{ width = 2, scale = 2 } == 4)
+        Slider.new():set(0.5)
+    ",
+    )
+    .exec()?;
+
+    let err = error_message(&lua, "set_volume(101)");
+    assert!(err.contains("bad argument `volume` to `set_volume"));
+    assert!(err.contains("must be in range 0..=100"));
+    // Failed checks name the binding's location as well, like failed conversions
+    #[cfg(feature = "error-location")]
+    assert!(err.contains("to `set_volume (at tests/validation.rs:"));
+
+    assert!(error_message(&lua, "rename('')").contains("must not be empty"));
+    assert!(
+        error_message(&lua, "rename('long orc name')").contains("must have a length of at most 8")
+    );
+    assert!(error_message(&lua, "rename('an orc')").contains("can't contain spaces"));
+    assert!(error_message(&lua, "rename('orc', 'o')").contains("bad argument `alias`"));
+
+    let err = error_message(&lua, "resize{ width = 2, scale = 3 }");
+    assert!(err.contains("bad argument `scale` to `resize"));
+
+    let err = error_message(&lua, "Slider.new():set(2)");
+    assert!(err.contains("bad argument `value` to `Slider:set"));
+
+    // Kept functions aren't checked when called from Rust
+    assert_eq!(resize(2.0, 3.0), 6.0);
+    Ok(())
+}